@@ -1,10 +1,12 @@
+#![allow(dead_code)]
+
 use kiss3d::camera::Camera;
 use kiss3d::context::Context;
 use kiss3d::light::Light;
 use kiss3d::resource::{Effect, Material, Mesh, ShaderAttribute, ShaderUniform};
 use kiss3d::scene::{ObjectData, SceneNode};
 use kiss3d::window::Window;
-use nalgebra::{Isometry3, Matrix3, Matrix4, Point3, Translation3, UnitQuaternion, Vector3};
+use nalgebra::{Isometry3, Matrix3, Matrix4, Point3, UnitQuaternion, Vector3};
 use std::cell::RefCell;
 use std::rc::Rc;
 
@@ -24,7 +26,7 @@ trait Node {
 struct Cell {
     live: Live,
     birth_day: u64,
-    sceneNode: SceneNode,
+    scene_node: SceneNode,
 }
 
 struct Universe {
@@ -37,7 +39,7 @@ impl Cell {
         Cell {
             live: Live::Alive,
             birth_day: 0,
-            sceneNode: SceneNode::new_empty(),
+            scene_node: SceneNode::new_empty(),
         }
     }
 }
@@ -127,6 +129,12 @@ pub struct NormalMaterial {
     scale: ShaderUniform<Matrix3<f32>>,
 }
 
+impl Default for NormalMaterial {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl NormalMaterial {
     pub fn new() -> NormalMaterial {
         let mut shader = Effect::new_from_str(NORMAL_VERTEX_SRC, NORMAL_FRAGMENT_SRC);
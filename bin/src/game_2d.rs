@@ -1,221 +1,370 @@
-use std::io::{stdout, Read, Write};
-
-use piston_window;
-use piston_window::color::BLACK;
-use piston_window::types::Color;
-use piston_window::Window;
-use rand::Rng;
+use std::io::{stdout, Write};
+
+use life_core::{
+    color_map, BitBoard, BlockRule, ContinuousLife, ContinuousRule, Engine, Grid, GrowingUniverse,
+    HashLife, History, LargerThanLife, Lattice, LtlRule, Margolus, Noise, Rng, Rule, Rule1d,
+    RuleFile, RuleLoader, SparseLife, Topology, Universe, Universe1d, Update,
+};
+use piston_window::{AdvancedWindow, Button, Key, PressEvent, Window};
+use termion::event::Key as TermKey;
+use termion::input::TermRead;
 use termion::raw::IntoRawMode;
 
-#[derive(PartialEq)]
-enum Live {
-    Alive,
-    Dead,
+/// What the 2d frontends run, as chosen on the command line.
+pub struct Settings {
+    pub rule: Rule,
+    /// A Larger-than-Life rule, run on its own engine in place of `rule`.
+    pub ltl: Option<LtlRule>,
+    /// A Margolus block rule, likewise run on its own engine.
+    pub block: Option<BlockRule>,
+    /// A Lenia or SmoothLife rule, likewise run on its own engine.
+    pub continuous: Option<ContinuousRule>,
+    /// A Golly rule file, likewise run on its own engine.
+    pub rule_file: Option<RuleFile>,
+    /// A one-dimensional rule, run on its own engine and drawn as a spacetime
+    /// diagram that scrolls down the view.
+    pub elementary: Option<Rule1d>,
+    /// The bounded grid from the rulestring's suffix. Only the dense and
+    /// bitboard engines have edges; `main` keeps it from the growing engine,
+    /// and the others ignore it.
+    pub grid: Option<Grid>,
+    /// `dense`, `bitboard`, `sparse`, `hashlife` or `growing`.
+    pub engine: String,
+    /// Each frame advances `2^step` generations.
+    pub step: u8,
+    /// Threads the dense and bitboard engines step on.
+    pub threads: usize,
+    /// Seeds the soup and the colours, so a run can be replayed.
+    pub seed: u64,
+    /// Number of past frames kept to step back to; 0 keeps none.
+    pub history: usize,
+    /// Every this many frames the history keeps a full snapshot, and only the
+    /// cells that changed in between.
+    pub snapshot_every: usize,
+    /// Random births and deaths after every frame, drawn from the seed.
+    pub noise: Noise,
+    /// The order the dense engine updates cells in.
+    pub update: Update,
 }
 
-struct Cell {
-    live: Live,
-    color: Color,
-    birth_day: u64,
-}
+impl Settings {
+    /// The size of the board: the grid's where the rulestring gave one, and
+    /// otherwise the `width` x `height` that fits the view.
+    fn board_size(&self, width: usize, height: usize) -> (usize, usize) {
+        let (width, height) = match self.grid {
+            Some(grid) => (grid.width.unwrap_or(width), grid.height.unwrap_or(height)),
+            None => (width, height),
+        };
+        // Margolus blocks only tile boards of even size, and continuous rules
+        // sum neighbourhoods through the FFT on sides that are powers of two
+        // unless the grid asks for others.
+        match (self.block, self.continuous, self.grid) {
+            (Some(_), _, _) => (width & !1, height & !1),
+            (None, Some(_), None) => (power_of_two_below(width), power_of_two_below(height)),
+            _ => (width, height),
+        }
+    }
 
-struct Board {
-    board: Vec<Vec<Cell>>,
-    width: usize,
-    height: usize,
-}
+    /// Whether cells take values between dead and alive, drawn through
+    /// `color_map` in 255ths.
+    fn is_continuous(&self) -> bool {
+        self.continuous.is_some()
+    }
 
-const DIRECTIONS: [[i32; 2]; 8] = [
-    [-1, -1],
-    [-1, 0],
-    [-1, 1],
-    [0, -1],
-    [0, 1],
-    [1, -1],
-    [1, 0],
-    [1, 1],
-];
+    fn topology(&self) -> Topology {
+        self.grid.map_or(Topology::Plane, |grid| grid.topology)
+    }
 
-impl Board {
-    fn new(width: usize, height: usize) -> Board {
-        let mut board: Vec<Vec<Cell>> = Vec::new();
-        for _ in 0..height {
-            let mut line: Vec<Cell> = Vec::new();
-            for w in 0..width {
-                line.push(Cell {
-                    live: Live::Alive,
-                    color: BLACK,
-                    birth_day: 0,
-                })
-            }
-            board.push(line);
-        }
-        Board {
-            board,
-            width,
-            height,
+    /// Whether the engine has edges, so the view never needs to be larger
+    /// than the board.
+    fn is_bounded(&self) -> bool {
+        self.ltl.is_some()
+            || self.block.is_some()
+            || self.continuous.is_some()
+            || self.rule_file.is_some()
+            || self.elementary.is_some()
+            || matches!(self.engine.as_str(), "dense" | "bitboard")
+    }
+
+    fn lattice(&self) -> Lattice {
+        if self.ltl.is_some()
+            || self.block.is_some()
+            || self.continuous.is_some()
+            || self.rule_file.is_some()
+            || self.elementary.is_some()
+        {
+            Lattice::Square
+        } else {
+            self.rule.lattice()
         }
     }
-    fn set(&mut self, w: usize, h: usize, cell: Cell) {
-        self.board[h][w] = cell
+
+    /// Number of cell states, counting dead and alive.
+    fn states(&self) -> u8 {
+        match (&self.ltl, &self.rule_file) {
+            (Some(ltl), _) => ltl.states,
+            (None, Some(file)) => file.states(),
+            (None, None) => self.rule.states(),
+        }
     }
+}
 
-    fn is_alive(&self, w: usize, h: usize) -> bool {
-        self.board[h][w].live == Live::Alive
+/// Builds the engine named in `settings`, with a random soup filling the
+/// `width` x `height` rectangle at the origin.
+fn random_engine(
+    settings: &Settings,
+    rng: &mut Rng,
+    width: usize,
+    height: usize,
+) -> Box<dyn Engine> {
+    if let Some(rule) = &settings.rule_file {
+        let mut loader =
+            RuleLoader::with_topology(width, height, rule.clone(), settings.topology());
+        loader.set_threads(settings.threads);
+        // Every state is as likely as any other.
+        for h in 0..height as i64 {
+            for w in 0..width as i64 {
+                loader.set_state(w, h, rng.below(rule.states() as u64) as u8);
+            }
+        }
+        return Box::new(loader);
+    }
+    if let Some(rule) = settings.elementary {
+        // Only the first generation is a soup; the rest of the board is
+        // filled by the rule.
+        let mut row = Universe1d::new(width, height, rule);
+        for w in 0..width as i64 {
+            row.set(w, 0, rng.gen_bool(1.0 / 2.0));
+        }
+        return Box::new(row);
     }
-    fn alive_neighbors_count(&self, w: usize, h: usize) -> u8 {
-        let mut count: u8 = 0;
-        for dir in DIRECTIONS {
-            let _w: i32 = w as i32 + dir[0];
-            let _h = h as i32 + dir[1];
-            if _w < 0 || _w as usize >= self.width || _h < 0 || _h as usize >= self.height {
-                continue;
+    if let Some(rule) = settings.block {
+        let mut block = Margolus::with_topology(width, height, rule, settings.topology());
+        block.set_threads(settings.threads);
+        // Sand starts as a cloud in the top half with room to fall.
+        let (rows, density) = match rule {
+            BlockRule::SAND => (height / 2, 1.0 / 4.0),
+            _ => (height, 1.0 / 2.0),
+        };
+        for h in 0..rows as i64 {
+            for w in 0..width as i64 {
+                block.set(w, h, rng.gen_bool(density));
             }
-            if self.is_alive(_w as usize, _h as usize) {
-                count += 1;
+        }
+        return Box::new(block);
+    }
+    if let Some(rule) = settings.continuous {
+        let mut life = ContinuousLife::new(width, height, rule);
+        life.set_threads(settings.threads);
+        // A patch of noise in the middle, with room round it for whatever
+        // comes out of it to move.
+        for h in height / 4..height * 3 / 4 {
+            for w in width / 4..width * 3 / 4 {
+                life.set_value(w as i64, h as i64, rng.gen_f64());
             }
         }
-        count
+        return Box::new(life);
     }
-
-    // calculate board live cells and dead cells count
-    fn cell_statics(&mut self) -> (usize, usize) {
-        let mut lives: usize = 0;
-
-        for h in 0..self.height {
-            for w in 0..self.width {
-                if self.board[h][w].live == Live::Alive {
-                    lives += 1;
-                }
+    let mut engine: Box<dyn Engine> = match (&settings.ltl, settings.engine.as_str()) {
+        (Some(rule), _) => {
+            let mut ltl =
+                LargerThanLife::with_topology(width, height, rule.clone(), settings.topology());
+            ltl.set_threads(settings.threads);
+            Box::new(ltl)
+        }
+        (None, "dense") => {
+            let mut universe = Universe::with_topology(width, height, settings.topology());
+            universe.set_rule(settings.rule);
+            universe.set_threads(settings.threads);
+            if settings.update != Update::Synchronous {
+                universe.set_update(settings.update, rng.next_u64());
+            }
+            Box::new(universe)
+        }
+        (None, "bitboard") => {
+            let mut bits =
+                BitBoard::with_topology(width, height, settings.rule, settings.topology());
+            bits.set_threads(settings.threads);
+            Box::new(bits)
+        }
+        (None, "sparse") => Box::new(SparseLife::new(settings.rule)),
+        (None, "hashlife") => Box::new(HashLife::new(settings.rule)),
+        (None, "growing") => {
+            let mut growing = GrowingUniverse::new(width, height, settings.rule);
+            growing.set_threads(settings.threads);
+            growing.set_shrink(true);
+            Box::new(growing)
+        }
+        // `--engine` only takes the engines above.
+        _ => unreachable!("unknown engine {}", settings.engine),
+    };
+    // Under a colour rule every colour gets an equal share of the soup.
+    let colors = settings.rule.colors();
+    for h in 0..height as i64 {
+        for w in 0..width as i64 {
+            match rng.gen_bool(1.0 / 2.0) {
+                true if colors > 1 => engine.set_state(w, h, 1 + rng.below(colors as u64) as u8),
+                alive => engine.set(w, h, alive),
             }
         }
-        let deads = self.height * self.width - lives;
-        (lives, deads)
     }
+    engine
 }
 
-pub fn add_one(x: i32) -> i32 {
-    x + 1
+/// Builds the engine for a view of `width` x `height` cells, and shrinks the
+/// view to the board where that is smaller.
+fn engine_for_view(
+    settings: &Settings,
+    rng: &mut Rng,
+    width: usize,
+    height: usize,
+) -> (Box<dyn Engine>, usize, usize) {
+    let (board_width, board_height) = settings.board_size(width, height);
+    let engine = random_engine(settings, rng, board_width, board_height);
+    if settings.is_bounded() {
+        (engine, width.min(board_width), height.min(board_height))
+    } else {
+        (engine, width, height)
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn it_works() {
-        assert_eq!(3, add_one(2));
-    }
+/// What the keys shared by the console and the 2d window ask for.
+enum Control {
+    /// Stops or restarts the run.
+    Pause,
+    /// Goes back one frame.
+    Back,
+    /// Goes forward one frame: through the history while there is one ahead,
+    /// and otherwise by stepping the engine.
+    Forward,
+    /// Goes back to the oldest frame kept.
+    First,
+    /// Goes to the newest frame kept.
+    Last,
 }
 
-struct Universe {
-    twin: Vec<Board>,
-    iboard: usize,
-    now: u64,
-    width: usize,
-    height: usize,
+/// The running engine with its history. Stepping on from a frame in the past
+/// branches off there, dropping the frames that came after it.
+struct Player {
+    engine: Box<dyn Engine>,
+    noise: Noise,
+    rng: Rng,
+    /// The board the noise falls on when it has no regions of its own.
+    board: (usize, usize),
+    history: Option<History>,
+    paused: bool,
+    /// Generations per frame.
+    step: u64,
 }
 
-impl Universe {
-    fn new(width: usize, height: usize) -> Universe {
-        let mut u: Universe = Universe {
-            iboard: 0,
-            twin: vec![],
-            now: 0,
-            width: width,
-            height: height,
+impl Player {
+    fn new(
+        engine: Box<dyn Engine>,
+        settings: &Settings,
+        rng: Rng,
+        board: (usize, usize),
+    ) -> Player {
+        let mut player = Player {
+            engine,
+            noise: settings.noise.clone(),
+            rng,
+            board,
+            history: (settings.history > 0)
+                .then(|| History::new(settings.snapshot_every.max(1), settings.history)),
+            paused: false,
+            step: 1 << settings.step,
         };
-        u.twin.push(Board::new(width, height));
-        u.twin.push(Board::new(width, height));
+        player.record();
+        player
+    }
 
-        for h in 0..height {
-            for w in 0..width {
-                let live: Live;
-                if rand::thread_rng().gen_bool(1.0 / 2.0) {
-                    live = Live::Alive;
-                } else {
-                    live = Live::Dead;
-                }
-                u.twin[0].set(
-                    w,
-                    h,
-                    Cell {
-                        live,
-                        color: BLACK,
-                        birth_day: 0,
-                    },
-                )
-            }
+    fn record(&mut self) {
+        if let Some(history) = &mut self.history {
+            history.record(self.engine.as_ref());
         }
-        u
-    }
-    fn get_now_board(&mut self) -> &Board {
-        &self.twin[self.iboard]
     }
 
-    fn get_pre_board(&mut self) -> &Board {
-        &self.twin[(self.iboard + 1) % 2]
+    /// Steps the engine by a frame unless paused.
+    fn frame(&mut self) {
+        if !self.paused {
+            self.advance();
+        }
     }
-    fn get_board(&mut self, i: usize) -> &Board {
-        &self.twin[i]
+
+    fn advance(&mut self) {
+        self.engine.step(self.step);
+        let (width, height) = self.board;
+        self.noise
+            .apply(self.engine.as_mut(), width, height, &mut self.rng);
+        self.record();
     }
 
-    fn tick(&mut self) {
-        let prev_i = self.iboard;
-        let now_i = (prev_i + 1) % 2;
-        self.now += 1;
-        for h in 0..self.height {
-            for w in 0..self.width {
-                let mut live: Live;
-                match self.get_board(prev_i).alive_neighbors_count(w, h) {
-                    0..=1 => {
-                        live = Live::Dead;
-                    }
-                    3 => {
-                        live = Live::Alive;
-                    }
-                    4..=u8::MAX => {
-                        live = Live::Dead;
-                    }
-                    _ => {
-                        if self.get_board(prev_i).is_alive(w, h) {
-                            live = Live::Alive;
-                        } else {
-                            live = Live::Dead;
-                        }
-                    }
-                }
-                self.twin[now_i].set(
-                    w,
-                    h,
-                    Cell {
-                        live,
-                        color: BLACK,
-                        birth_day: self.now,
-                    },
-                )
+    fn control(&mut self, control: Control) {
+        if let Control::Pause = control {
+            self.paused = !self.paused;
+            return;
+        }
+        self.paused = true;
+        let Some(history) = &self.history else {
+            if let Control::Forward = control {
+                self.advance();
             }
+            return;
+        };
+        let generation = self.engine.generation();
+        let target = match control {
+            Control::Back => history.before(generation),
+            Control::Forward => history.after(generation),
+            Control::First => history.first(),
+            _ => history.last(),
+        };
+        match (target, control) {
+            (Some(target), _) => {
+                history.restore(self.engine.as_mut(), target);
+            }
+            (None, Control::Forward) => self.advance(),
+            _ => {}
         }
-        self.iboard = now_i;
+    }
+
+    fn status(&self, seed: u64) -> String {
+        let history = self.history.as_ref().map_or(0, History::memory_usage);
+        let tiles = match self.engine.active_tiles() {
+            Some(tiles) => format!(" | active tiles {}", tiles.len()),
+            None => String::new(),
+        };
+        format!(
+            "seed {} | generation {}{} | population {}{} | memory {} KiB",
+            seed,
+            self.engine.generation(),
+            if self.paused { " (paused)" } else { "" },
+            self.engine.population(),
+            tiles,
+            (self.engine.memory_usage() + history) / 1024
+        )
     }
 }
 
-pub fn console_game() {
-    let mut width: usize = 0;
-    let mut height: usize = 0;
+pub fn add_one(x: i32) -> i32 {
+    x + 1
+}
 
-    match termion::terminal_size() {
-        Ok(size) => {
-            width = size.0 as usize;
-            height = size.1 as usize;
-        }
+pub fn console_game(settings: &Settings) {
+    let (width, height) = match termion::terminal_size() {
+        Ok(size) => (size.0 as usize, size.1 as usize),
         Err(err) => {
             panic!("{}", err)
         }
-    }
-    let mut universe: Universe = Universe::new(width, height);
+    };
+    // The bottom line is kept for the status.
+    let height = height.saturating_sub(1).max(1);
+    let mut rng = Rng::new(settings.seed);
+    let board = settings.board_size(width, height);
+    let (engine, width, height) = engine_for_view(settings, &mut rng, width, height);
+    let mut player = Player::new(engine, settings, Rng::new(rng.next_u64()), board);
 
     let mut stdout = stdout().into_raw_mode().unwrap();
+    let mut keys = termion::async_stdin().keys();
 
     writeln!(
         stdout,
@@ -228,25 +377,70 @@ pub fn console_game() {
 
     writeln!(stdout, "{}", termion::clear::All).unwrap();
     loop {
-        universe.tick();
+        // Space pauses, `,` and `.` step back and forward, Home and End go to
+        // either end of the history and `q` quits.
+        while let Some(Ok(key)) = keys.next() {
+            let control = match key {
+                TermKey::Char(' ') => Control::Pause,
+                TermKey::Char(',') => Control::Back,
+                TermKey::Char('.') => Control::Forward,
+                TermKey::Home => Control::First,
+                TermKey::End => Control::Last,
+                TermKey::Char('q') | TermKey::Esc => {
+                    write!(stdout, "{}", termion::cursor::Show).unwrap();
+                    return;
+                }
+                _ => continue,
+            };
+            player.control(control);
+        }
+        player.frame();
+        let engine = &player.engine;
 
-        let board = universe.get_now_board();
-        for h in 0..board.height {
+        for h in 0..height {
             writeln!(stdout, "{}", termion::cursor::Goto(1, h as u16 + 1)).unwrap();
-            for w in 0..board.width {
-                if board.is_alive(w, h) {
-                    write!(stdout, "◼").unwrap();
-                } else {
-                    write!(stdout, " ").unwrap();
+            for w in 0..width {
+                let state = engine.state(w as i64, h as i64);
+                if settings.is_continuous() {
+                    let shade = [" ", "░", "▒", "▓", "█"][state as usize * 5 / 256];
+                    write!(stdout, "{}", shade).unwrap();
+                    continue;
+                }
+                if settings.rule.colors() > 1 && state > 0 {
+                    let color = termion::color::Fg(FAMILY_ANSI[state as usize - 1]);
+                    write!(
+                        stdout,
+                        "{}◼{}",
+                        color,
+                        termion::color::Fg(termion::color::Reset)
+                    )
+                    .unwrap();
+                    continue;
                 }
+                let cell = match state {
+                    0 => " ",
+                    1 => "◼",
+                    // Dying cells of Generations rules, and the other states
+                    // of rule files.
+                    _ => "◻",
+                };
+                write!(stdout, "{}", cell).unwrap();
             }
         }
+        write!(
+            stdout,
+            "{}{}{}",
+            termion::cursor::Goto(1, height as u16 + 1),
+            termion::clear::CurrentLine,
+            player.status(settings.seed)
+        )
+        .unwrap();
         stdout.flush().unwrap();
         std::thread::sleep(std::time::Duration::from_millis(1));
     }
 }
 
-pub fn game_2d() {
+pub fn game_2d(settings: &Settings) {
     let mut window: piston_window::PistonWindow =
         piston_window::WindowSettings::new("game of life 2d", [1920, 1080])
             .exit_on_esc(true)
@@ -258,19 +452,55 @@ pub fn game_2d() {
     let color_dead = piston_window::color::BLACK;
 
     const CELL_LENGTH: f64 = 5.0;
-    let cell_Rec = |w: f64, h: f64| -> [f64; 4] {
+    let cell_rec = |w: f64, h: f64| -> [f64; 4] {
         let _w = w * CELL_LENGTH;
         let _h = h * CELL_LENGTH;
         [_w, _h, CELL_LENGTH, CELL_LENGTH]
     };
-    let height = (window_size.height as f64 / CELL_LENGTH) as usize;
-    let width = (window_size.width as f64 / CELL_LENGTH) as usize;
-
-    let mut universe: Universe = Universe::new(width, height);
+    let lattice = settings.lattice();
+    let height = (window_size.height / CELL_LENGTH) as usize;
+    let width = (window_size.width / (CELL_LENGTH * lattice.column_width())) as usize;
+
+    let mut rng = Rng::new(settings.seed);
+    let board = settings.board_size(width, height);
+    let (engine, width, height) = engine_for_view(settings, &mut rng, width, height);
+    let mut player = Player::new(engine, settings, Rng::new(rng.next_u64()), board);
+    // Top left corner of the view; the arrow keys move it over unbounded engines.
+    let mut origin: (i64, i64) = (0, 0);
+    // Colours belong to the view: a cell keeps its colour for as long as it
+    // stays alive and gets a fresh one when it is born.
+    let mut alive = vec![false; width * height];
+    let mut colors = vec![color_dead; width * height];
+    let mut show_tiles = false;
 
     while let Some(event) = window.next() {
+        if let Some(Button::Keyboard(key)) = event.press_args() {
+            // Even steps keep hexagon rows and triangle directions in place.
+            let pan = (width / 20 * 2) as i64;
+            match key {
+                Key::Left => origin.0 -= pan,
+                Key::Right => origin.0 += pan,
+                Key::Up => origin.1 -= pan,
+                Key::Down => origin.1 += pan,
+                Key::Space => player.control(Control::Pause),
+                Key::Comma => player.control(Control::Back),
+                Key::Period => player.control(Control::Forward),
+                Key::Home => player.control(Control::First),
+                Key::End => player.control(Control::Last),
+                // Outlines the tiles the dense engine recomputes next tick.
+                Key::T => show_tiles = !show_tiles,
+                _ => {}
+            }
+            // Whatever is on screen now is new to the view.
+            alive.fill(false);
+        }
+        window.set_title(format!(
+            "game of life 2d | {}",
+            player.status(settings.seed)
+        ));
         window.draw_2d(&event, |context, graphics, _device| {
-            universe.tick();
+            player.frame();
+            let engine = &player.engine;
 
             piston_window::rectangle(
                 color_dead,
@@ -281,31 +511,99 @@ pub fn game_2d() {
 
             for h in 0..height {
                 for w in 0..width {
-                    if universe.twin[universe.iboard].is_alive(w, h) {
-                        let color_alive: piston_window::types::Color;
-
-                        if universe.twin[(universe.iboard + 1) % 2].is_alive(w, h) {
-                            color_alive =
-                                universe.twin[(universe.iboard + 1) % 2].board[h][w].color;
+                    let i = h * width + w;
+                    let was_alive = alive[i];
+                    let state = engine.state(origin.0 + w as i64, origin.1 + h as i64);
+                    alive[i] = state == 1;
+                    if alive[i] && !was_alive {
+                        colors[i] = COLORS[rng.below(COLORS.len() as u64) as usize];
+                    }
+                    if state > 0 {
+                        // Rule files bring their own colours.
+                        let color = match &settings.rule_file {
+                            Some(rule) => rule.color(state),
+                            None if settings.is_continuous() => color_map(state as f64 / 255.0),
+                            // Colour rules mean something by their colours.
+                            None if settings.rule.colors() > 1 => FAMILIES[state as usize - 1],
+                            // Rows scroll every frame, so a colour per cell
+                            // would only flicker.
+                            None if settings.elementary.is_some() => piston_window::color::WHITE,
+                            None => fade(colors[i], state, settings.states()),
+                        };
+                        if lattice == Lattice::Square {
+                            piston_window::rectangle(
+                                color,
+                                cell_rec(w as f64, h as f64),
+                                context.transform,
+                                graphics,
+                            );
                         } else {
-                            color_alive = colors[rand::thread_rng().gen_range(0..colors.len())];
+                            let outline: Vec<[f64; 2]> = lattice
+                                .outline(w as i64, h as i64)
+                                .iter()
+                                .map(|&[x, y]| [x * CELL_LENGTH, y * CELL_LENGTH])
+                                .collect();
+                            piston_window::polygon(color, &outline, context.transform, graphics);
                         }
-                        universe.twin[universe.iboard].board[h][w].color = color_alive;
-                        piston_window::rectangle(
-                            color_alive,
-                            cell_Rec(w as f64, h as f64),
-                            context.transform,
-                            graphics,
-                        );
                     }
                 }
             }
+            if show_tiles {
+                let border = piston_window::Rectangle::new_border([1.0, 1.0, 1.0, 0.5], 0.5);
+                for tile in engine.active_tiles().unwrap_or_default() {
+                    let x = (tile.x - origin.0) as f64 * CELL_LENGTH * lattice.column_width();
+                    let y = (tile.y - origin.1) as f64 * CELL_LENGTH;
+                    let size = [
+                        tile.width as f64 * CELL_LENGTH * lattice.column_width(),
+                        tile.height as f64 * CELL_LENGTH,
+                    ];
+                    border.draw(
+                        [x, y, size[0], size[1]],
+                        &context.draw_state,
+                        context.transform,
+                        graphics,
+                    );
+                }
+            }
             // std::thread::sleep(std::time::Duration::from_millis(500));
         });
     }
 }
 
-const colors: [piston_window::types::Color; 13] = [
+/// The largest power of two no larger than `n`, or 1.
+fn power_of_two_below(n: usize) -> usize {
+    match n {
+        0 => 1,
+        _ => 1 << n.ilog2(),
+    }
+}
+
+/// Darkens `color` for the dying states of Generations rules, the more the
+/// closer the cell is to dead.
+fn fade(color: piston_window::types::Color, state: u8, states: u8) -> piston_window::types::Color {
+    if state <= 1 {
+        return color;
+    }
+    let f = (states - state) as f32 / (states - 1) as f32;
+    [color[0] * f, color[1] * f, color[2] * f, color[3]]
+}
+
+/// The colours of colour rules such as Immigration and QuadLife, in the
+/// window and on the console.
+const FAMILIES: [piston_window::types::Color; 4] = [
+    piston_window::color::RED,
+    piston_window::color::BLUE,
+    piston_window::color::GREEN,
+    piston_window::color::YELLOW,
+];
+const FAMILY_ANSI: [termion::color::AnsiValue; 4] = [
+    termion::color::AnsiValue(9),
+    termion::color::AnsiValue(12),
+    termion::color::AnsiValue(10),
+    termion::color::AnsiValue(11),
+];
+
+const COLORS: [piston_window::types::Color; 13] = [
     piston_window::color::BLUE,
     piston_window::color::CYAN,
     piston_window::color::GREEN,
@@ -320,3 +618,13 @@ const colors: [piston_window::types::Color; 13] = [
     piston_window::color::TEAL,
     piston_window::color::YELLOW,
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_works() {
+        assert_eq!(3, add_one(2));
+    }
+}
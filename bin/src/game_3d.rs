@@ -1,4 +1,4 @@
-use rand::random;
+use life_core::{Rng, Rule3d, Universe3d};
 
 #[derive(Clone)]
 struct Cell {
@@ -17,151 +17,97 @@ impl Cell {
         }
     }
 
-    fn born(&mut self, wd: &mut kiss3d::window::Window, x: f32, y: f32, z: f32) {
+    fn born(&mut self, wd: &mut kiss3d::window::Window, rng: &mut Rng, x: f32, y: f32, z: f32) {
         if !self.alive {
             self.alive = true;
             self.birth_time = std::time::SystemTime::now();
             self.scene = wd.add_cube(0.9, 0.9, 0.9);
-            self.scene.set_color(random(), random(), random());
+            self.scene.set_color(
+                rng.gen_f64() as f32,
+                rng.gen_f64() as f32,
+                rng.gen_f64() as f32,
+            );
             self.scene
                 .set_local_translation(kiss3d::nalgebra::Translation3::new(x, y, z))
         }
     }
-    fn kill(&mut self, wd: &mut kiss3d::window::Window, x: f32, y: f32, z: f32) {
+    fn kill(&mut self, wd: &mut kiss3d::window::Window) {
         if self.alive {
             self.alive = false;
             wd.remove_node(&mut self.scene);
         }
     }
-
-    fn alive(&self) -> bool {
-        self.alive
-    }
 }
 
+/// The cubes drawn for a `Universe3d`, one per live cell.
 struct Universe {
-    len_x: usize,
-    len_y: usize,
-    len_z: usize,
-    universe_idx: usize,
-    universe_twin: Vec<Vec<Vec<Vec<Cell>>>>,
+    inner: Universe3d,
+    cells: Vec<Cell>,
+    rng: Rng,
 }
 
 impl Universe {
-    fn new(lenx: usize, leny: usize, lenz: usize) -> Universe {
-        let mut u = Universe {
-            len_x: lenx,
-            len_y: leny,
-            len_z: lenz,
-            universe_idx: 0,
-            universe_twin: vec![],
-        };
-        u.universe_twin =
-            vec![vec![vec![vec![Cell::new(); lenz as usize]; leny as usize]; lenx as usize]; 2];
-        u
+    fn new(size: [usize; 3], rule: Rule3d, seed: u64) -> Universe {
+        let [lenx, leny, lenz] = size;
+        Universe {
+            inner: Universe3d::new(lenx, leny, lenz, rule),
+            cells: vec![Cell::new(); lenx * leny * lenz],
+            rng: Rng::new(seed),
+        }
     }
 
+    /// Fills the middle half of the box along each axis with a soup of one
+    /// cell in five, sparse enough that most 3D rules do not crowd it out.
     fn init(&mut self, wd: &mut kiss3d::window::Window) {
-        for ix in 0..self.len_x {
-            for iy in 0..self.len_y {
-                for iz in 0..self.len_z {
-                    if rand::random() {
-                        self.universe_twin[0][ix][iy][iz].born(wd, ix as f32, iy as f32, iz as f32);
+        let size = self.inner.size();
+        for ix in size[0] / 4..size[0] - size[0] / 4 {
+            for iy in size[1] / 4..size[1] - size[1] / 4 {
+                for iz in size[2] / 4..size[2] - size[2] / 4 {
+                    if self.rng.gen_bool(1.0 / 5.0) {
+                        self.inner.set(ix as i64, iy as i64, iz as i64, true);
                     }
                 }
             }
         }
+        self.draw(wd);
     }
 
     fn tick(&mut self, wd: &mut kiss3d::window::Window) {
-        let prev_idx = self.universe_idx;
-        let now_idx = (self.universe_idx + 1) % 2;
-        for ix in 0..self.len_x {
-            for iy in 0..self.len_y {
-                for iz in 0..self.len_z {
-                    let mut count: u8 = 0;
-                    for direction in DIRECTIONS {
-                        let x = ix as i32 + direction[0];
-                        let y = iy as i32 + direction[1];
-                        let z = iz as i32 + direction[2];
-                        if x >= 0
-                            && x < self.len_x as i32
-                            && y >= 0
-                            && y < self.len_y as i32
-                            && z >= 0
-                            && z < self.len_z as i32
-                            && self.universe_twin[prev_idx][x as usize][y as usize][z as usize]
-                                .alive
-                        {
-                            count += 1;
-                        }
-                    }
+        self.inner.tick();
+        self.draw(wd);
+    }
 
-                    if grim_reaper(self.universe_twin[prev_idx][ix][iy][iz].alive(), count) {
-                        self.universe_twin[now_idx][ix][iy][iz]
-                            .born(wd, ix as f32, iy as f32, iz as f32)
+    /// Adds cubes for the cells born and removes those of the cells that died.
+    fn draw(&mut self, wd: &mut kiss3d::window::Window) {
+        let [lenx, leny, lenz] = self.inner.size();
+        for iz in 0..lenz {
+            for iy in 0..leny {
+                for ix in 0..lenx {
+                    let cell = &mut self.cells[(iz * leny + iy) * lenx + ix];
+                    if self.inner.get(ix as i64, iy as i64, iz as i64) {
+                        cell.born(wd, &mut self.rng, ix as f32, iy as f32, iz as f32)
                     } else {
-                        self.universe_twin[now_idx][ix][iy][iz]
-                            .kill(wd, ix as f32, iy as f32, iz as f32)
+                        cell.kill(wd)
                     }
                 }
             }
         }
-        self.universe_idx = now_idx;
     }
 }
 
-fn grim_reaper(live: bool, count: u8) -> bool {
-    match count {
-        2 => live,
-        3 => true,
-        _ => false,
-    }
-}
-
-const DIRECTIONS: [[i32; 3]; 8 + 9 + 9] = [
-    // up
-    [0, 0, 1],
-    [0, 1, 1],
-    [0, -1, 1],
-    [1, 0, 1],
-    [-1, 0, 1],
-    [1, 1, 1],
-    [1, -1, 1],
-    [-1, -1, 1],
-    [-1, 1, 1],
-    //middle
-    [0, 1, 0],
-    [0, -1, 0],
-    [1, 0, 0],
-    [-1, 0, 0],
-    [1, 1, 0],
-    [1, -1, 0],
-    [-1, 1, 0],
-    [-1, -1, 0],
-    // down
-    [0, 0, -1],
-    [0, 1, -1],
-    [0, -1, -1],
-    [1, 0, -1],
-    [-1, 0, -1],
-    [1, 1, -1],
-    [1, -1, -1],
-    [-1, 1, -1],
-    [-1, -1, -1],
-];
-
-pub fn game_3d() {
-    let mut window = kiss3d::window::Window::new("Conway's game of life");
-    let lenx = 40;
-    let leny = 40;
-    let lenz = 1;
-    let mut universe = Universe::new(lenx, leny, lenz);
+/// Runs `rule` in a box of `size` = `[x, y, z]` cells, from a soup and
+/// colours drawn from `seed`.
+pub fn game_3d(rule: Rule3d, size: [usize; 3], seed: u64) {
+    let mut window = kiss3d::window::Window::new(&format!("3d life | {}", rule));
+    let mut universe = Universe::new(size, rule, seed);
     universe.init(&mut window);
 
-    // let eye = kiss3d::nalgebra::Point3::new(lenx as f32 / 2.0, leny as f32 / 2.0, lenx as f32);
-    let eye = kiss3d::nalgebra::Point3::new(lenx as f32 / 2.0, leny as f32 / 2.0, lenx as f32);
-    let at = kiss3d::nalgebra::Point3::new(lenx as f32 / 2.0, leny as f32 / 2.0, 0.0);
+    // Look at the middle of the box from off one corner, far enough out to
+    // see all of it.
+    let [lenx, leny, lenz] = size.map(|n| n as f32);
+    let at = kiss3d::nalgebra::Point3::new(lenx / 2.0, leny / 2.0, lenz / 2.0);
+    let far = lenx.max(leny).max(lenz);
+    let eye = kiss3d::nalgebra::Point3::new(at.x + far, at.y + far * 0.6, at.z + far * 1.2);
     let mut first_person = kiss3d::camera::FirstPerson::new(eye, at);
     while window.render_with_camera(&mut first_person) {
         universe.tick(&mut window);
@@ -0,0 +1,215 @@
+use std::str::FromStr;
+
+use life_core::{Engine, Graph, GraphLife, GraphRule, Penrose, PenroseKind, Rng, TileShape};
+use piston_window::{AdvancedWindow, Button, Key, PressEvent, Window};
+
+/// The most deflations `penrose:` takes. Each one multiplies the tiles by
+/// about 2.6, so twelve already make half a million, and much further on the
+/// rounding that matches their corners up would merge distinct ones.
+const MAX_DEFLATIONS: usize = 12;
+
+/// Where the graph comes from, as given to `--graph`: `small-world:N,K,P`
+/// or `scale-free:N,M` for a generated one, `penrose:P3,N` or `penrose:P2,N`
+/// for the tiles of a Penrose tiling deflated `N` times, from 1 to
+/// `MAX_DEFLATIONS`, or the path of a file.
+#[derive(Debug)]
+pub enum GraphArg {
+    SmallWorld {
+        nodes: usize,
+        k: usize,
+        p: f64,
+    },
+    ScaleFree {
+        nodes: usize,
+        m: usize,
+    },
+    Penrose {
+        kind: PenroseKind,
+        deflations: usize,
+    },
+    File(Graph),
+}
+
+impl GraphArg {
+    /// The graph, drawing any random choices from `rng`, and the Penrose
+    /// tiling it was made from, if any.
+    fn build(&self, rng: &mut Rng) -> (Graph, Option<Penrose>) {
+        match *self {
+            GraphArg::SmallWorld { nodes, k, p } => (Graph::small_world(nodes, k, p, rng), None),
+            GraphArg::ScaleFree { nodes, m } => (Graph::scale_free(nodes, m, rng), None),
+            GraphArg::Penrose { kind, deflations } => {
+                let tiling = Penrose::new(kind, deflations);
+                (tiling.graph(), Some(tiling))
+            }
+            GraphArg::File(ref graph) => (graph.clone(), None),
+        }
+    }
+}
+
+impl FromStr for GraphArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<GraphArg, String> {
+        let numbers = |params: &str| -> Result<Vec<f64>, String> {
+            params
+                .split(',')
+                .map(|n| {
+                    n.trim()
+                        .parse()
+                        .map_err(|_| format!("invalid number in `{}`", s))
+                })
+                .collect()
+        };
+        match s.split_once(':') {
+            Some(("small-world", params)) => match numbers(params)?.as_slice() {
+                &[nodes, k, p] if (0.0..=1.0).contains(&p) => Ok(GraphArg::SmallWorld {
+                    nodes: nodes as usize,
+                    k: k as usize,
+                    p,
+                }),
+                _ => Err(format!(
+                    "expected small-world:N,K,P with P from 0 to 1, not `{}`",
+                    s
+                )),
+            },
+            Some(("scale-free", params)) => match numbers(params)?.as_slice() {
+                &[nodes, m] if m >= 1.0 => Ok(GraphArg::ScaleFree {
+                    nodes: nodes as usize,
+                    m: m as usize,
+                }),
+                _ => Err(format!(
+                    "expected scale-free:N,M with M from 1, not `{}`",
+                    s
+                )),
+            },
+            Some(("penrose", params)) => {
+                let (kind, deflations) = params.split_once(',').unwrap_or((params, "6"));
+                let deflations = deflations
+                    .trim()
+                    .parse()
+                    .ok()
+                    .filter(|n| (1..=MAX_DEFLATIONS).contains(n))
+                    .ok_or_else(|| {
+                        format!(
+                            "expected penrose:P3,N or penrose:P2,N with N from 1 to {}, not `{}`",
+                            MAX_DEFLATIONS, s
+                        )
+                    })?;
+                Ok(GraphArg::Penrose {
+                    kind: kind.parse().map_err(|e| format!("{}", e))?,
+                    deflations,
+                })
+            }
+            _ => {
+                let text = std::fs::read_to_string(s).map_err(|e| format!("{}: {}", s, e))?;
+                Ok(GraphArg::File(
+                    text.parse().map_err(|e| format!("{}: {}", s, e))?,
+                ))
+            }
+        }
+    }
+}
+
+/// Runs `rule` on the nodes of the graph, drawn where the graph file put
+/// them or else by a force layout, from a soup and layout drawn from `seed`.
+/// The tiles of a Penrose tiling are drawn as themselves, and `S` saves them
+/// as an SVG image. Space pauses and `.` steps a paused run.
+pub fn game_graph(graph: &GraphArg, rule: GraphRule, seed: u64) {
+    let mut rng = Rng::new(seed);
+    let (graph, tiling) = graph.build(&mut rng);
+    let layout = graph.layout(100, &mut rng);
+    let edges: Vec<(usize, usize)> = graph.edges().collect();
+    let mut life = GraphLife::new(graph, rule);
+    for node in 0..layout.len() {
+        life.set_alive(node, rng.gen_bool(1.0 / 2.0));
+    }
+
+    let mut window: piston_window::PistonWindow =
+        piston_window::WindowSettings::new("game of life on a graph", [1080, 1080])
+            .exit_on_esc(true)
+            .build()
+            .unwrap();
+    let mut paused = false;
+    while let Some(event) = window.next() {
+        if let Some(Button::Keyboard(key)) = event.press_args() {
+            match key {
+                Key::Space => paused = !paused,
+                Key::Period if paused => life.tick(),
+                Key::S => {
+                    if let Some(tiling) = &tiling {
+                        let path = format!("penrose-{}.svg", life.generation());
+                        let svg = tiling.svg(1080, |tile| life.is_alive(tile));
+                        match std::fs::write(&path, svg) {
+                            Ok(()) => println!("saved {}", path),
+                            Err(err) => println!("{}: {}", path, err),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        window.set_title(format!(
+            "game of life on a graph | {} | generation {}{} | population {} of {}",
+            life.rule(),
+            life.generation(),
+            if paused { " (paused)" } else { "" },
+            life.population(),
+            layout.len()
+        ));
+        let size = window.size();
+        window.draw_2d(&event, |context, graphics, _device| {
+            if !paused {
+                life.tick();
+            }
+            piston_window::clear(piston_window::color::BLACK, graphics);
+            // The unit square of the layout, less a margin the size of a node.
+            let side = size.width.min(size.height);
+            let node = (side / (layout.len() as f64).sqrt() / 4.0).clamp(2.0, 12.0);
+            let at = |[x, y]: [f64; 2]| {
+                [
+                    node + x * (side - 2.0 * node),
+                    node + y * (side - 2.0 * node),
+                ]
+            };
+            if let Some(tiling) = &tiling {
+                // The tiling fills the square from (-1, -1) to (1, 1).
+                for (n, tile) in tiling.tiles().iter().enumerate() {
+                    let color = match tile.shape {
+                        _ if life.is_alive(n) => piston_window::color::LIME,
+                        TileShape::Thin | TileShape::Kite => [0.16, 0.16, 0.23, 1.0],
+                        TileShape::Thick | TileShape::Dart => [0.23, 0.23, 0.33, 1.0],
+                    };
+                    let corners = tile
+                        .corners
+                        .map(|[x, y]| at([(x + 1.0) / 2.0, (y + 1.0) / 2.0]));
+                    piston_window::polygon(color, &corners, context.transform, graphics);
+                }
+                return;
+            }
+            for &(a, b) in &edges {
+                let ([x0, y0], [x1, y1]) = (at(layout[a]), at(layout[b]));
+                piston_window::line(
+                    [0.4, 0.4, 0.4, 1.0],
+                    0.5,
+                    [x0, y0, x1, y1],
+                    context.transform,
+                    graphics,
+                );
+            }
+            for (n, &position) in layout.iter().enumerate() {
+                let color = if life.is_alive(n) {
+                    piston_window::color::LIME
+                } else {
+                    [0.2, 0.2, 0.2, 1.0]
+                };
+                let [x, y] = at(position);
+                piston_window::ellipse(
+                    color,
+                    [x - node / 2.0, y - node / 2.0, node, node],
+                    context.transform,
+                    graphics,
+                );
+            }
+        });
+    }
+}
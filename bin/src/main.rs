@@ -1,8 +1,87 @@
-use clap::Parser;
+use std::str::FromStr;
+
+use clap::{CommandFactory, ErrorKind, Parser};
+use life_core::{
+    BlockRule, ContinuousRule, GraphRule, Grid, Lattice, LtlRule, Noise, Rng, Rule, Rule1d, Rule3d,
+    RuleFile, Update,
+};
 
 pub mod cube;
 pub mod game_2d;
 pub mod game_3d;
+pub mod game_graph;
+
+/// A rule with an optional Golly bounded-grid suffix, e.g. `B3/S23:T100,80`.
+/// Larger-than-Life rules, which start with their radius, Margolus block
+/// rules, Lenia and SmoothLife rules and Golly `.rule` files, read from the
+/// path given, are kept apart.
+#[derive(Debug)]
+struct RuleArg {
+    rule: Rule,
+    ltl: Option<LtlRule>,
+    block: Option<BlockRule>,
+    continuous: Option<ContinuousRule>,
+    rule_file: Option<RuleFile>,
+    grid: Option<Grid>,
+}
+
+impl FromStr for RuleArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<RuleArg, String> {
+        let (rule, grid) = match s.split_once(':') {
+            Some((rule, grid)) => (rule, Some(grid.parse().map_err(|e| format!("{}", e))?)),
+            None => (s, None),
+        };
+        let mut arg = RuleArg {
+            rule: Rule::default(),
+            ltl: None,
+            block: None,
+            continuous: None,
+            rule_file: None,
+            grid,
+        };
+        if rule.ends_with(".rule") {
+            let text = std::fs::read_to_string(rule).map_err(|e| format!("{}: {}", rule, e))?;
+            arg.rule_file = Some(text.parse().map_err(|e| format!("{}: {}", rule, e))?);
+        } else if let Ok(block) = rule.parse() {
+            arg.block = Some(block);
+        } else if let Ok(continuous) = rule.parse() {
+            arg.continuous = Some(continuous);
+        } else if rule.trim_start().starts_with(['R', 'r']) {
+            arg.ltl = Some(rule.parse().map_err(|e| format!("{}", e))?);
+        } else {
+            arg.rule = rule.parse().map_err(|e| format!("{}", e))?;
+        }
+        Ok(arg)
+    }
+}
+
+/// The size of the 3d box, e.g. `30x30x30`.
+#[derive(Debug)]
+struct BoxSize([usize; 3]);
+
+impl FromStr for BoxSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<BoxSize, String> {
+        let sizes: Vec<usize> = s
+            .split(['x', 'X', ','])
+            .map(|n| {
+                n.trim()
+                    .parse()
+                    .map_err(|_| format!("invalid size `{}`", s))
+            })
+            .collect::<Result<_, _>>()?;
+        match sizes.as_slice() {
+            &[x, y, z] if x > 0 && y > 0 && z > 0 => Ok(BoxSize([x, y, z])),
+            _ => Err(format!(
+                "expected three sizes above 0 as XxYxZ, not `{}`",
+                s
+            )),
+        }
+    }
+}
 
 /// Simple program to greet a person
 #[derive(Parser, Debug)]
@@ -15,6 +94,77 @@ struct Args {
     /// Number of times to greet
     #[clap(short, long, default_value_t = 1)]
     ttl: u8,
+
+    /// Rule in B/S notation, e.g. B36/S23 (HighLife), B2/S (Seeds) or /2/3 (Brian's Brain),
+    /// Immigration and QuadLife, where newborns take their parents' colour, or B36/S23/Q likewise,
+    /// a Larger-than-Life rule such as R5,C0,M1,S34..58,B34..45,NM (Bosco's Rule), a Margolus
+    /// block rule such as Critters, Tron, BBM, Sand or MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15,
+    /// a continuous rule such as Lenia, R=13,T=10,m=0.15,s=0.015, SmoothLife or ri=7,ra=21,dt=0.1,
+    /// or the path of a Golly .rule file such as WireWorld.rule, optionally with a bounded grid such as :T100,80 (torus) or :K100*,80 (Klein bottle).
+    /// The 3d mode takes Bays' notation such as 4555 or 5766, or B/S sets of up to 26 neighbours
+    /// such as B5/S45, with ,NN for the 6 von Neumann neighbours. The 1d and console-1d modes take
+    /// a Wolfram code such as 110, W30 or W4294967295,R2, or a totalistic code such as T20,R2.
+    /// The graph mode takes B/S sets of up to 31 neighbours such as B3/S2..4,12.
+    /// [default: B3/S23, 4555 in 3d or 110 in 1d]
+    #[clap(short, long)]
+    rule: Option<String>,
+
+    /// Graph for the graph mode: small-world:N,K,P (N nodes in a ring, each joined to the K nearest
+    /// either side, with edges rewired with probability P), scale-free:N,M (N nodes, each joined
+    /// to M earlier ones), penrose:P3,N or penrose:P2,N (the rhombs or kites and darts of a
+    /// Penrose tiling deflated N times, from 1 to 12, each tile joined to those it shares a corner with) or the
+    /// path of an edge or adjacency list
+    #[clap(long, default_value = "small-world:400,2,0.05")]
+    graph: game_graph::GraphArg,
+
+    /// Size of the 3d box, XxYxZ
+    #[clap(long, default_value = "30x30x30")]
+    size: BoxSize,
+
+    /// Stepping engine for the console and 2d modes: dense, bitboard, sparse, hashlife or growing
+    /// (dense on a board that grows to follow the cells)
+    #[clap(
+        short,
+        long,
+        default_value = "dense",
+        value_parser = ["dense", "bitboard", "sparse", "hashlife", "growing"]
+    )]
+    engine: String,
+
+    /// Advance 2^STEP generations per frame, up to 2^56
+    #[clap(short, long, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=56))]
+    step: u8,
+
+    /// Threads to step the dense and bitboard engines on; 0 uses every core
+    #[clap(long, default_value_t = 1)]
+    threads: usize,
+
+    /// Seed for the soup and colours, to replay a run; a random one is picked and printed if
+    /// left out
+    #[clap(long)]
+    seed: Option<u64>,
+
+    /// Number of past frames the console and 2d modes keep to step back to with `,` (`.` steps
+    /// forward, space pauses); 0 keeps none
+    #[clap(long, default_value_t = 1000)]
+    history: usize,
+
+    /// Keep a full snapshot of every this many frames in the history, and only the cells that
+    /// changed in between
+    #[clap(long, default_value_t = 32)]
+    snapshot_every: usize,
+
+    /// Random births and deaths after every frame of the console and 2d modes, e.g. B0.001
+    /// (any dead cell), B2=0.05 (dead cells with 2 neighbours), D0.01 (any live cell) or
+    /// B0.01/@0,0,40x30 (only in that region), joined with slashes [default: none]
+    #[clap(long)]
+    noise: Option<Noise>,
+
+    /// Order the dense engine updates cells in: sync (all at once), sweep (one at a time, row by
+    /// row), random (one at a time in a random order) or alpha=0.5 (all at once, each with that
+    /// probability)
+    #[clap(long, default_value = "sync")]
+    update: Update,
 }
 
 fn main() {
@@ -22,13 +172,136 @@ fn main() {
     if args.mode.is_empty() {
         args.mode = String::from("2d");
     }
+    if args.threads == 0 {
+        args.threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    }
+    let seed = args.seed.unwrap_or_else(Rng::random_seed);
+    println!("seed {}", seed);
+    if args.mode == "3d" {
+        let rule: Rule3d = parse_rule(args.rule.as_deref().unwrap_or("4555"));
+        game_3d::game_3d(rule, args.size.0, seed);
+        return;
+    }
+    if args.mode == "graph" {
+        let rule: GraphRule = parse_rule(args.rule.as_deref().unwrap_or("B3/S23"));
+        game_graph::game_graph(&args.graph, rule, seed);
+        return;
+    }
+    let elementary = match args.mode.as_str() {
+        "1d" | "console-1d" => Some(parse_rule::<Rule1d>(args.rule.as_deref().unwrap_or("110"))),
+        _ => None,
+    };
+    let rule: RuleArg = match elementary {
+        Some(_) => parse_rule("B3/S23"),
+        None => parse_rule(args.rule.as_deref().unwrap_or("B3/S23")),
+    };
+    let plain = rule.ltl.is_none()
+        && rule.block.is_none()
+        && rule.continuous.is_none()
+        && rule.rule_file.is_none()
+        && elementary.is_none();
+    if args.update != Update::Synchronous && (args.engine != "dense" || !plain) {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "only the dense engine updates cells asynchronously",
+            )
+            .exit()
+    }
+    if rule.rule.colors() > 1 && !matches!(args.engine.as_str(), "dense" | "growing") {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                format!(
+                    "only the dense and growing engines keep the colours of {}",
+                    rule.rule
+                ),
+            )
+            .exit()
+    }
+    if plain && rule.grid.is_some() && args.engine == "growing" {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "the growing engine follows its cells and cannot run on a bounded grid",
+            )
+            .exit()
+    }
+    if let Some(reason) = plain
+        .then(|| unsupported(&args.engine, &rule.rule))
+        .flatten()
+    {
+        Args::command()
+            .error(ErrorKind::ArgumentConflict, reason)
+            .exit()
+    }
+    let settings = game_2d::Settings {
+        rule: rule.rule,
+        ltl: rule.ltl,
+        block: rule.block,
+        continuous: rule.continuous,
+        rule_file: rule.rule_file,
+        elementary,
+        grid: rule.grid,
+        engine: args.engine,
+        step: args.step,
+        threads: args.threads,
+        seed,
+        history: args.history,
+        snapshot_every: args.snapshot_every,
+        noise: args.noise.unwrap_or_default(),
+        update: args.update,
+    };
     match args.mode.as_str() {
-        "console" => game_2d::console_game(), // TODO exit game-of-life on key('q' or 'ESC') hit
-        "2d" => game_2d::game_2d(),
-        "3d" => game_3d::game_3d(),
+        "console" | "console-1d" => game_2d::console_game(&settings),
+        "2d" | "1d" => game_2d::game_2d(&settings),
         "cube" => cube::cube(),
         _ => {
             println!("unknown game mode");
         }
     }
 }
+
+/// Why `engine` cannot run `rule`, if it cannot. The colours of colour rules
+/// are checked apart.
+fn unsupported(engine: &str, rule: &Rule) -> Option<String> {
+    let unbounded = matches!(engine, "sparse" | "hashlife" | "growing");
+    if unbounded && rule.is_born(0) {
+        Some(format!(
+            "the {} engine cannot run {}, which brings empty space to life",
+            engine, rule
+        ))
+    } else if engine != "dense" && engine != "growing" && rule.states() > 2 {
+        Some(format!(
+            "the {} engine cannot run the Generations rule {}",
+            engine, rule
+        ))
+    } else if matches!(engine, "bitboard" | "hashlife") && rule.lattice() != Lattice::Square {
+        Some(format!(
+            "the {} engine cannot run {}, which is not on the square lattice",
+            engine, rule
+        ))
+    } else if engine != "dense" && engine != "growing" && !rule.is_totalistic() {
+        Some(format!(
+            "the {} engine cannot run the non-totalistic rule {}",
+            engine, rule
+        ))
+    } else {
+        None
+    }
+}
+
+/// Parses `--rule` for the mode at hand, exiting as clap would on an invalid one.
+fn parse_rule<T: FromStr>(rule: &str) -> T
+where
+    T::Err: std::fmt::Display,
+{
+    rule.parse().unwrap_or_else(|err| {
+        Args::command()
+            .error(
+                ErrorKind::InvalidValue,
+                format!("invalid value '{}' for '--rule': {}", rule, err),
+            )
+            .exit()
+    })
+}
@@ -2,8 +2,6 @@
 http://www.piclist.com/tecHREF/datafile/charset/extractor/font12x16pic8.asm.txt
  */
 pub mod ascii_map {
-    use fastrand::bool;
-
     pub fn get(c: char) -> Vec<u8> {
         match c {
             '!' => vec![
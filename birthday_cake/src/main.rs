@@ -1,26 +1,23 @@
 use std::cell::RefCell;
-use std::f64::consts::{FRAC_1_PI, FRAC_2_PI, FRAC_PI_2, PI};
+use std::f64::consts::PI;
 use std::rc::Rc;
 
-use fastrand::{f32, f64, u32, usize};
 use kiss3d::camera::FirstPerson;
 use kiss3d::light::Light;
 use kiss3d::ncollide3d::na;
 use kiss3d::ncollide3d::procedural::{utils, IndexBuffer, TriMesh};
 use kiss3d::ncollide3d::simba::scalar::RealField;
 use kiss3d::resource::Mesh;
-use kiss3d::scene::{PlanarSceneNode, SceneNode};
+use kiss3d::scene::SceneNode;
 use kiss3d::window::Window;
-use nalgebra::{
-    Isometry2, Isometry3, Point2, Point3, Translation, Translation3, UnitQuaternion, Vector3, U24,
-};
+use life_core::{Noise, Rng, Topology, Universe};
+use nalgebra::{Point2, Point3, Translation3, UnitQuaternion, Vector3};
 
-use crate::color::color::{BLACK, WHITE, YELLOW};
-use crate::life::Live;
+use crate::color::color::BLACK;
 
 mod ascii;
+#[allow(dead_code, clippy::module_inception)]
 mod color;
-mod life;
 
 // Black	#000000	(0,0,0)
 // White	#FFFFFF	(255,255,255)
@@ -85,23 +82,23 @@ pub fn xunit_cylinder<N: RealField + Copy>(nsubdiv: u32, grid_width: u32, r: u32
     let mut curr_u = N::zero();
     for _ in 0..nsubdiv {
         uvs.push(Point2::new(curr_u, na::zero()));
-        curr_u = curr_u + invsubdiv;
+        curr_u += invsubdiv;
     }
 
     // top ring uvs
     curr_u = na::zero();
     for _ in 0..nsubdiv {
         uvs.push(Point2::new(curr_u, na::one()));
-        curr_u = curr_u + invsubdiv;
+        curr_u += invsubdiv;
     }
 
     /*
      * Adjust normals.
      */
     for n in normals.iter_mut() {
-        n.x = n.x * na::convert(2.0);
+        n.x *= na::convert(2.0);
         n.y = na::zero();
-        n.z = n.z * na::convert(2.0);
+        n.z *= na::convert(2.0);
     }
 
     normals.push(Vector3::y()); // top cap
@@ -112,13 +109,13 @@ pub fn xunit_cylinder<N: RealField + Copy>(nsubdiv: u32, grid_width: u32, r: u32
 
     for i in indices[..top_start_id].iter_mut() {
         if i.x.y >= nsubdiv {
-            i.x.y = i.x.y - nsubdiv;
+            i.x.y -= nsubdiv;
         }
         if i.y.y >= nsubdiv {
-            i.y.y = i.y.y - nsubdiv;
+            i.y.y -= nsubdiv;
         }
         if i.z.y >= nsubdiv {
-            i.z.y = i.z.y - nsubdiv;
+            i.z.y -= nsubdiv;
         }
     }
 
@@ -146,15 +143,40 @@ fn set_color(sc: &mut SceneNode, c: rgb::RGB8) {
     sc.set_color(c.r as f32 / 255.0, c.g as f32 / 255.0, c.b as f32 / 255.0)
 }
 
-fn rand_color() -> rgb::RGB8 {
-    rgb::RGB8::new(
-        fastrand::u8(0..=255),
-        fastrand::u8(0..=255),
-        fastrand::u8(0..=255),
-    )
+fn rand_color(rng: &mut Rng) -> rgb::RGB8 {
+    let [r, g, b, ..] = rng.next_u64().to_le_bytes();
+    rgb::RGB8::new(r, g, b)
+}
+
+/// The value given after `flag` on the command line.
+fn arg(flag: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    let i = args.iter().position(|arg| arg == flag)?;
+    Some(args.get(i + 1).cloned().unwrap_or_default())
+}
+
+/// The seed given as `--seed N`, or a random one.
+fn seed() -> u64 {
+    match arg("--seed") {
+        Some(seed) => seed.parse().expect("--seed takes a number"),
+        None => Rng::random_seed(),
+    }
+}
+
+/// The random births and deaths given as `--noise`, e.g. `B0.001/D0.001`, or
+/// by default a dead cell coming alive one tick in a hundred.
+fn noise() -> Noise {
+    let noise = arg("--noise").unwrap_or_else(|| String::from("B0.01"));
+    noise
+        .parse()
+        .unwrap_or_else(|err| panic!("--noise: {}", err))
 }
 
 fn main() {
+    let mut rng = Rng::new(seed());
+    println!("seed {}", rng.seed());
+    let noise = noise();
+
     let mut window = Window::new("Happy Birthday! @EXEC!");
     window.set_light(Light::StickToCamera);
 
@@ -171,15 +193,11 @@ fn main() {
     let width = (PI * 2.0 * radious as f64 / grid_width as f64) as usize;
 
     let mut nodes: Vec<SceneNode> = Vec::new();
-    let scale = Vector3::new(
-        radious as f32 * 2.0,
-        grid_width as f32,
-        radious as f32 * 2.0,
-    );
+    let scale = Vector3::new(radious * 2.0, grid_width as f32, radious * 2.0);
     for level in 0..=height {
         for i in 0..=width {
             let mut mesh_cylinder = window.add_mesh(mesh.clone(), scale);
-            set_color(&mut mesh_cylinder, rand_color());
+            set_color(&mut mesh_cylinder, rand_color(&mut rng));
             mesh_cylinder.set_local_translation(Translation3::new(
                 0.0,
                 level as f32 * grid_width as f32,
@@ -191,21 +209,22 @@ fn main() {
             // ));
             let rot = UnitQuaternion::from_axis_angle(
                 &Vector3::y_axis(),
-                i as f32 * grid_width as f32 / radious as f32,
+                i as f32 * grid_width as f32 / radious,
             );
             mesh_cylinder.set_local_rotation(rot);
             nodes.push(mesh_cylinder);
         }
     }
 
-    let mut universe = life::Universe::new(width + 1, height + 1);
+    let mut universe = Universe::with_topology(width + 1, height + 1, Topology::Torus);
+    universe.randomize(|| rng.gen_bool(1.0 / 2.0));
 
     let mut text_body = window.add_group();
     let mut display_text = |st: &str, linenum: u32| {
         let char_vec: Vec<char> = st.chars().collect();
 
         let mut text_line = text_body.add_group();
-        for (idxCh, ch) in char_vec.iter().enumerate() {
+        for (idx_ch, ch) in char_vec.iter().enumerate() {
             let mut one_char = text_line.add_group();
             let vc = ascii::ascii_map::get(*ch);
             let vc2 = vc
@@ -217,11 +236,9 @@ fn main() {
                 })
                 .collect::<Vec<u8>>();
             for line in 0..16 {
-                let mut first: u32 = vc2[line * 3 + 0] as u32;
-                first = first << 8;
-                let mut second: u32 = vc2[line * 3 + 1] as u32;
-                second = second << 4;
-                let mut third: u32 = vc2[line * 3 + 2] as u32;
+                let first: u32 = (vc2[line * 3] as u32) << 8;
+                let second: u32 = (vc2[line * 3 + 1] as u32) << 4;
+                let third: u32 = vc2[line * 3 + 2] as u32;
                 let row = first + second + third;
 
                 for i in 1..=12 {
@@ -240,7 +257,7 @@ fn main() {
                 }
             }
             one_char.set_local_translation(Translation3::new(
-                (idxCh as u32 * (text_grid_width * 13)) as f32,
+                (idx_ch as u32 * (text_grid_width * 13)) as f32,
                 height as f32 * grid_width as f32,
                 0.0 + (linenum * 25 * text_grid_width) as f32,
             ));
@@ -285,14 +302,18 @@ fn main() {
         // window.draw_line(&zero, &zmax, &Point3::new(0.0, 0.0, 1.0));
 
         // top.prepend_to_local_rotation(&rot);
-        let mut pk = |h: usize, w: usize, live: Live| {
-            if live.eq(&Live::Alive) {
-                set_color(&mut nodes[w + h * (width + 1)], rand_color());
-            } else {
-                set_color(&mut nodes[w + h * (width + 1)], BLACK);
+        universe.tick();
+        let (w, h) = (universe.width(), universe.height());
+        noise.apply(&mut universe, w, h, &mut rng);
+        for h in 0..universe.height() {
+            for w in 0..universe.width() {
+                if universe.is_alive(w, h) {
+                    set_color(&mut nodes[w + h * (width + 1)], rand_color(&mut rng));
+                } else {
+                    set_color(&mut nodes[w + h * (width + 1)], BLACK);
+                }
             }
-        };
-        universe.tick(&mut pk);
+        }
 
         for node in &mut nodes {
             node.prepend_to_local_rotation(&rot);
@@ -1,3 +1,5 @@
+#[cfg(target_arch = "wasm32")]
+#[allow(special_module_name)]
 mod main;
 // Entry point for wasm
 #[cfg(target_arch = "wasm32")]
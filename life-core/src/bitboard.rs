@@ -0,0 +1,389 @@
+use std::borrow::Cow;
+use std::mem::size_of;
+
+use crate::board::{Color, BLACK};
+use crate::engine::Engine;
+use crate::lattice::Lattice;
+use crate::parallel::for_each_band;
+use crate::rule::Rule;
+use crate::topology::{Edge, Topology};
+
+/// A finite board with one bit per cell, packed 64 cells to a `u64` along
+/// each row.
+///
+/// `tick` counts the neighbours of 64 cells at once with a tree of bitwise
+/// adders, so it touches no per-cell memory. Colour and birth day are kept in
+/// separate planes that only exist once asked for.
+pub struct BitBoard {
+    width: usize,
+    height: usize,
+    /// Words per row. Bits past `width` in the last word are always zero.
+    stride: usize,
+    cells: Vec<u64>,
+    next: Vec<u64>,
+    now: u64,
+    rule: Rule,
+    topology: Topology,
+    colors: Option<Vec<Color>>,
+    birth_days: Option<Vec<u64>>,
+    threads: usize,
+}
+
+/// Panics unless `rule` is a two-state totalistic rule on the square lattice,
+/// the only kind the adders can count out.
+fn assert_runnable(rule: &Rule) {
+    assert_eq!(rule.states(), 2, "BitBoard cannot run Generations rules");
+    assert!(
+        rule.is_totalistic(),
+        "BitBoard cannot run non-totalistic rules"
+    );
+    assert_eq!(
+        rule.lattice(),
+        Lattice::Square,
+        "BitBoard runs square lattices only"
+    );
+}
+
+/// Sum and carry of three one-bit numbers, 64 lanes at a time.
+fn add3(a: u64, b: u64, c: u64) -> (u64, u64) {
+    let ab = a ^ b;
+    (ab ^ c, (a & b) | (ab & c))
+}
+
+impl BitBoard {
+    pub fn new(width: usize, height: usize, rule: Rule) -> BitBoard {
+        BitBoard::with_topology(width, height, rule, Topology::Plane)
+    }
+
+    pub fn with_topology(width: usize, height: usize, rule: Rule, topology: Topology) -> BitBoard {
+        assert_runnable(&rule);
+        let stride = width.div_ceil(64);
+        BitBoard {
+            width,
+            height,
+            stride,
+            cells: vec![0; stride * height],
+            next: vec![0; stride * height],
+            now: 0,
+            rule,
+            topology,
+            colors: None,
+            birth_days: None,
+            threads: 1,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) {
+        assert_runnable(&rule);
+        self.rule = rule;
+    }
+
+    /// Splits each tick into bands of rows stepped on `threads` threads. The
+    /// result is the same for any number of threads.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    /// Starts tracking a colour per cell; newborns are black.
+    pub fn enable_colors(&mut self) {
+        self.colors
+            .get_or_insert_with(|| vec![BLACK; self.width * self.height]);
+    }
+
+    /// Starts tracking the generation in which each cell was last born.
+    pub fn enable_birth_days(&mut self) {
+        let now = self.now;
+        self.birth_days
+            .get_or_insert_with(|| vec![now; self.width * self.height]);
+    }
+
+    pub fn color(&self, w: usize, h: usize) -> Option<Color> {
+        self.colors.as_ref().map(|c| c[h * self.width + w])
+    }
+
+    pub fn set_color(&mut self, w: usize, h: usize, color: Color) {
+        if let Some(colors) = &mut self.colors {
+            colors[h * self.width + w] = color;
+        }
+    }
+
+    pub fn birth_day(&self, w: usize, h: usize) -> Option<u64> {
+        self.birth_days.as_ref().map(|b| b[h * self.width + w])
+    }
+
+    pub fn is_alive(&self, w: usize, h: usize) -> bool {
+        self.cells[h * self.stride + w / 64] >> (w % 64) & 1 == 1
+    }
+
+    pub fn set_alive(&mut self, w: usize, h: usize, alive: bool) {
+        let word = &mut self.cells[h * self.stride + w / 64];
+        let bit = 1 << (w % 64);
+        if alive && *word & bit == 0 {
+            *word |= bit;
+            if let Some(birth_days) = &mut self.birth_days {
+                birth_days[h * self.width + w] = self.now;
+            }
+        } else if !alive {
+            *word &= !bit;
+        }
+    }
+
+    /// Mask of the bits in the last word of a row that lie on the board.
+    fn last_word_mask(&self) -> u64 {
+        match self.width % 64 {
+            0 => u64::MAX,
+            n => (1 << n) - 1,
+        }
+    }
+
+    /// The row at `h`, which may be one off either edge. Coming back across a
+    /// twisted edge, the row is mirrored.
+    fn row(&self, h: i64) -> Option<Cow<'_, [u64]>> {
+        let (_, r) = self.topology.locate(0, h, self.width, self.height)?;
+        let row = &self.cells[r * self.stride..(r + 1) * self.stride];
+        let crossed = h < 0 || h >= self.height as i64;
+        if !crossed || self.topology.edges().1 != Edge::Twist {
+            return Some(Cow::Borrowed(row));
+        }
+        let mut mirrored = vec![0; self.stride];
+        for w in 0..self.width {
+            let m = self.width - 1 - w;
+            mirrored[m / 64] |= (row[w / 64] >> (w % 64) & 1) << (m % 64);
+        }
+        Some(Cow::Owned(mirrored))
+    }
+
+    /// Word `i` of a row, flanked by the words holding the west and east
+    /// neighbour of each of its cells. Neighbours across the left and right
+    /// edges are left out.
+    fn shifted(&self, row: &Option<Cow<'_, [u64]>>, i: usize) -> (u64, u64, u64) {
+        let row = match row {
+            Some(row) => row,
+            None => return (0, 0, 0),
+        };
+        let mut west = row[i] << 1;
+        let mut east = row[i] >> 1;
+        if i > 0 {
+            west |= row[i - 1] >> 63;
+        }
+        if i < self.stride - 1 {
+            east |= row[i + 1] << 63;
+        }
+        (west, row[i], east)
+    }
+
+    /// Computes row `h` of the next generation into `out`.
+    fn step_row(&self, h: usize, out: &mut [u64], birth: &[u8], survival: &[u8]) {
+        let above = self.row(h as i64 - 1);
+        let middle = self.row(h as i64);
+        let below = self.row(h as i64 + 1);
+        let last = self.stride - 1;
+        let b_last = (self.width - 1) % 64;
+        for (i, out) in out.iter_mut().enumerate() {
+            let (mut nw, alive, mut ne) = self.shifted(&middle, i);
+            let (mut a0, a1, mut a2) = self.shifted(&above, i);
+            let (mut b0, b1, mut b2) = self.shifted(&below, i);
+            // The topology decides what lies beyond the left and right edges.
+            let beyond = |w: i64, dh: i64| Engine::get(self, w, h as i64 + dh) as u64;
+            if i == 0 {
+                a0 |= beyond(-1, -1);
+                nw |= beyond(-1, 0);
+                b0 |= beyond(-1, 1);
+            }
+            if i == last {
+                let w = self.width as i64;
+                a2 |= beyond(w, -1) << b_last;
+                ne |= beyond(w, 0) << b_last;
+                b2 |= beyond(w, 1) << b_last;
+            }
+
+            // Add the eight neighbour bits into a 4-bit count per lane.
+            let (s_a, c_a) = add3(a0, a1, a2);
+            let (s_b, c_b) = add3(b0, b1, b2);
+            let (s_c, c_c) = (nw ^ ne, nw & ne);
+            let (bit0, c_0) = add3(s_a, s_b, s_c);
+            let (t, t_4) = add3(c_a, c_b, c_c);
+            let (bit1, u_4) = (t ^ c_0, t & c_0);
+            let (bit2, bit3) = (t_4 ^ u_4, t_4 & u_4);
+
+            let count_is = |n: u8| {
+                let pick = |bit: u64, k: u8| if n >> k & 1 == 1 { bit } else { !bit };
+                pick(bit0, 0) & pick(bit1, 1) & pick(bit2, 2) & pick(bit3, 3)
+            };
+            let born = birth.iter().fold(0, |m, &n| m | count_is(n));
+            let survives = survival.iter().fold(0, |m, &n| m | count_is(n));
+            *out = (!alive & born) | (alive & survives);
+            if i == last {
+                *out &= self.last_word_mask();
+            }
+        }
+    }
+}
+
+impl Engine for BitBoard {
+    fn tick(&mut self) {
+        self.now += 1;
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        let (birth, survival): (Vec<u8>, Vec<u8>) = (
+            (0..=8).filter(|&n| self.rule.is_born(n)).collect(),
+            (0..=8).filter(|&n| self.rule.survives(n)).collect(),
+        );
+        let mut next = std::mem::take(&mut self.next);
+        let this = &*self;
+        for_each_band(&mut next, self.stride, self.threads, |first, rows| {
+            for (h, out) in (first..).zip(rows.chunks_mut(this.stride)) {
+                this.step_row(h, out, &birth, &survival);
+            }
+        });
+
+        if self.colors.is_some() || self.birth_days.is_some() {
+            for (i, (&old, &new)) in self.cells.iter().zip(&next).enumerate() {
+                let mut born = new & !old;
+                while born != 0 {
+                    let bit = born.trailing_zeros() as usize;
+                    born &= born - 1;
+                    let cell = i / self.stride * self.width + i % self.stride * 64 + bit;
+                    if let Some(colors) = &mut self.colors {
+                        colors[cell] = BLACK;
+                    }
+                    if let Some(birth_days) = &mut self.birth_days {
+                        birth_days[cell] = self.now;
+                    }
+                }
+            }
+        }
+        self.next = std::mem::replace(&mut self.cells, next);
+    }
+
+    fn generation(&self) -> u64 {
+        self.now
+    }
+
+    fn set_generation(&mut self, generation: u64) {
+        self.now = generation;
+    }
+
+    fn population(&self) -> u64 {
+        self.cells.iter().map(|w| w.count_ones() as u64).sum()
+    }
+
+    fn get(&self, x: i64, y: i64) -> bool {
+        self.topology
+            .locate(x, y, self.width, self.height)
+            .is_some_and(|(w, h)| self.is_alive(w, h))
+    }
+
+    fn set(&mut self, x: i64, y: i64, alive: bool) {
+        if let Some((w, h)) = self.topology.locate(x, y, self.width, self.height) {
+            self.set_alive(w, h, alive)
+        }
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64, u8)> {
+        let mut out = Vec::new();
+        for h in 0..self.height {
+            for w in 0..self.width {
+                let state = self.get(w as i64, h as i64) as u8;
+                if state != 0 {
+                    out.push((w as i64, h as i64, state));
+                }
+            }
+        }
+        out
+    }
+
+    fn memory_usage(&self) -> usize {
+        (self.cells.len() + self.next.len()) * size_of::<u64>()
+            + self
+                .colors
+                .as_ref()
+                .map_or(0, |c| c.len() * size_of::<Color>())
+            + self
+                .birth_days
+                .as_ref()
+                .map_or(0, |b| b.len() * size_of::<u64>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::soup;
+    use crate::topology::Axis;
+    use crate::universe::Universe;
+
+    fn compare(topology: Topology, threads: usize) {
+        let rule: Rule = "B36/S23".parse().unwrap();
+        let (width, height) = (130, 37);
+        let mut dense = Universe::with_topology(width, height, topology);
+        dense.set_rule(rule);
+        let mut bits = BitBoard::with_topology(width, height, rule, topology);
+        bits.enable_birth_days();
+        bits.set_threads(threads);
+        soup(
+            7,
+            0..width as i64,
+            0..height as i64,
+            &mut [&mut dense, &mut bits],
+        );
+        for _ in 0..30 {
+            dense.tick();
+            Engine::tick(&mut bits);
+            for h in 0..height {
+                for w in 0..width {
+                    assert_eq!(dense.is_alive(w, h), bits.is_alive(w, h), "({}, {})", w, h);
+                    if dense.is_alive(w, h) {
+                        let birth_day = dense.get_now_board().get(w, h).birth_day;
+                        assert_eq!(Some(birth_day), bits.birth_day(w, h));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn matches_dense_universe_on_plane() {
+        compare(Topology::Plane, 1);
+    }
+
+    #[test]
+    fn matches_dense_universe_on_torus() {
+        compare(Topology::Torus, 1);
+    }
+
+    #[test]
+    fn matches_dense_universe_on_twisted_surfaces() {
+        compare(Topology::Cylinder(Axis::Y), 1);
+        compare(Topology::KleinBottle(Axis::X), 1);
+        compare(Topology::KleinBottle(Axis::Y), 1);
+        compare(Topology::CrossSurface, 1);
+    }
+
+    #[test]
+    fn matches_dense_universe_with_threads() {
+        compare(Topology::Torus, 3);
+    }
+
+    #[test]
+    fn empty_boards_still_tick() {
+        for (width, height) in [(0, 5), (5, 0), (0, 0)] {
+            let mut bits = BitBoard::with_topology(width, height, Rule::CONWAY, Topology::Torus);
+            bits.tick();
+            assert_eq!((bits.generation(), bits.population()), (1, 0));
+        }
+    }
+}
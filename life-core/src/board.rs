@@ -0,0 +1,186 @@
+use crate::hensel;
+use crate::lattice::Lattice;
+use crate::topology::Topology;
+
+/// RGBA colour, laid out like `piston_window::types::Color`.
+pub type Color = [f32; 4];
+
+pub const BLACK: Color = [0.0, 0.0, 0.0, 1.0];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Live {
+    Alive,
+    Dead,
+    /// On its way from alive to dead under a Generations rule, `n`
+    /// generations after it stopped being alive. It does not count as a live
+    /// neighbour.
+    Dying(u8),
+}
+
+impl Live {
+    /// The Golly state number: 0 dead, 1 alive and 2 and up dying.
+    pub fn state(&self) -> u8 {
+        match self {
+            Live::Dead => 0,
+            Live::Alive => 1,
+            Live::Dying(n) => n + 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Cell {
+    pub live: Live,
+    pub color: Color,
+    /// Generation in which the cell was last born.
+    pub birth_day: u64,
+    /// Which colour, from 1, a live cell has under a colour rule such as
+    /// Immigration; 1 under any other rule.
+    pub family: u8,
+}
+
+impl Cell {
+    pub fn dead() -> Cell {
+        Cell {
+            live: Live::Dead,
+            color: BLACK,
+            birth_day: 0,
+            family: 0,
+        }
+    }
+}
+
+/// The Moore neighbourhood: offsets of the eight cells touching a cell.
+pub const DIRECTIONS: [[i32; 2]; 8] = [
+    [-1, -1],
+    [-1, 0],
+    [-1, 1],
+    [0, -1],
+    [0, 1],
+    [1, -1],
+    [1, 0],
+    [1, 1],
+];
+
+/// One generation of a finite `width` x `height` grid of cells.
+#[derive(Clone)]
+pub struct Board {
+    board: Vec<Vec<Cell>>,
+    width: usize,
+    height: usize,
+    topology: Topology,
+}
+
+impl Board {
+    pub fn new(width: usize, height: usize) -> Board {
+        Board::with_topology(width, height, Topology::Plane)
+    }
+
+    pub fn with_topology(width: usize, height: usize, topology: Topology) -> Board {
+        Board {
+            board: vec![vec![Cell::dead(); width]; height],
+            width,
+            height,
+            topology,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn get(&self, w: usize, h: usize) -> &Cell {
+        &self.board[h][w]
+    }
+
+    pub fn get_mut(&mut self, w: usize, h: usize) -> &mut Cell {
+        &mut self.board[h][w]
+    }
+
+    /// The rows of the board, top to bottom.
+    pub(crate) fn rows_mut(&mut self) -> &mut [Vec<Cell>] {
+        &mut self.board
+    }
+
+    pub fn set(&mut self, w: usize, h: usize, cell: Cell) {
+        self.board[h][w] = cell
+    }
+
+    pub fn is_alive(&self, w: usize, h: usize) -> bool {
+        self.board[h][w].live == Live::Alive
+    }
+
+    /// Counts the live cells among the eight neighbours of `(w, h)`, following the
+    /// board's topology at the edges.
+    pub fn alive_neighbors_count(&self, w: usize, h: usize) -> u8 {
+        self.alive_neighbors_count_on(Lattice::Square, w, h)
+    }
+
+    /// Counts the live cells among the neighbours of `(w, h)` on `lattice`.
+    pub fn alive_neighbors_count_on(&self, lattice: Lattice, w: usize, h: usize) -> u8 {
+        let mut count: u8 = 0;
+        for dir in lattice.offsets(w as i64, h as i64) {
+            let _w = w as i64 + dir[0] as i64;
+            let _h = h as i64 + dir[1] as i64;
+            if let Some((_w, _h)) = self.topology.locate(_w, _h, self.width, self.height) {
+                if self.is_alive(_w, _h) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    /// The live cells around `(w, h)` and the cell itself, one bit each, with
+    /// the bit of offset `(dx, dy)` at `(1 - dy) * 3 + (1 - dx)`.
+    pub fn neighborhood(&self, w: usize, h: usize) -> u16 {
+        let mut neighborhood = 0;
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (_w, _h) = (w as i64 + dx as i64, h as i64 + dy as i64);
+                if let Some((_w, _h)) = self.topology.locate(_w, _h, self.width, self.height) {
+                    if self.is_alive(_w, _h) {
+                        neighborhood |= hensel::bit(dx, dy);
+                    }
+                }
+            }
+        }
+        neighborhood
+    }
+
+    /// Counts the live neighbours of `(w, h)` on `lattice` by colour, those
+    /// of colour `c` at `c - 1`, as `Rule::inherit` takes them.
+    pub fn parents_on(&self, lattice: Lattice, w: usize, h: usize) -> [u8; 4] {
+        let mut parents = [0; 4];
+        for dir in lattice.offsets(w as i64, h as i64) {
+            let (x, y) = (w as i64 + dir[0] as i64, h as i64 + dir[1] as i64);
+            if let Some((x, y)) = self.topology.locate(x, y, self.width, self.height) {
+                let cell = self.get(x, y);
+                if cell.live == Live::Alive && (1..=4).contains(&cell.family) {
+                    parents[cell.family as usize - 1] += 1;
+                }
+            }
+        }
+        parents
+    }
+
+    // calculate board live cells and dead cells count
+    pub fn cell_statics(&self) -> (usize, usize) {
+        let lives = self
+            .board
+            .iter()
+            .flatten()
+            .filter(|cell| cell.live == Live::Alive)
+            .count();
+        let deads = self.height * self.width - lives;
+        (lives, deads)
+    }
+}
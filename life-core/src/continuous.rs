@@ -0,0 +1,674 @@
+use std::f64::consts::PI;
+use std::fmt;
+use std::mem::size_of;
+use std::str::FromStr;
+
+use crate::board::Color;
+use crate::engine::Engine;
+use crate::parallel::for_each_band;
+use crate::rule::ParseRuleError;
+
+/// Bert Chan's Lenia with a single ring: each cell's neighbourhood is a ring
+/// of radius `radius` weighted by a smooth bump, and a cell grows by `1 / time`
+/// of the growth function `2 exp(-(u - mu)^2 / 2 sigma^2) - 1` of its weighted
+/// neighbourhood `u` each generation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lenia {
+    /// `R`, in cells.
+    pub radius: f64,
+    /// `T`, the number of generations to a unit of time.
+    pub time: f64,
+    /// `m`, the neighbourhood that grows fastest.
+    pub mu: f64,
+    /// `s`, how far from `mu` a neighbourhood still grows.
+    pub sigma: f64,
+}
+
+impl Lenia {
+    /// The parameters of Orbium, Lenia's glider.
+    pub const ORBIUM: Lenia = Lenia {
+        radius: 13.0,
+        time: 10.0,
+        mu: 0.15,
+        sigma: 0.015,
+    };
+}
+
+/// Stephan Rafler's SmoothLife: each cell sees the filling `m` of the disc of
+/// radius `inner` round it and the filling `n` of the ring from there out to
+/// `outer`. Where `m` is low the cell is born when `n` lies between the birth
+/// bounds, where it is high it survives between the death bounds, with
+/// sigmoids of widths `alpha_n` and `alpha_m` in place of hard edges.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SmoothLife {
+    pub inner: f64,
+    pub outer: f64,
+    pub birth: (f64, f64),
+    pub death: (f64, f64),
+    pub alpha_n: f64,
+    pub alpha_m: f64,
+    /// 1 replaces each cell by the transition outright; smaller steps move
+    /// it that fraction of the way from 0 or 1.
+    pub dt: f64,
+}
+
+impl Default for SmoothLife {
+    /// The parameters of Rafler's paper, whose gliders travel diagonally.
+    fn default() -> SmoothLife {
+        SmoothLife {
+            inner: 7.0,
+            outer: 21.0,
+            birth: (0.278, 0.365),
+            death: (0.267, 0.445),
+            alpha_n: 0.028,
+            alpha_m: 0.147,
+            dt: 1.0,
+        }
+    }
+}
+
+impl SmoothLife {
+    /// The cell's next value, from `n` the filling of the ring and `m` that
+    /// of the disc, each from 0 to 1.
+    fn transition(&self, n: f64, m: f64) -> f64 {
+        let sigmoid = |x: f64, a: f64, alpha: f64| 1.0 / (1.0 + (-(x - a) * 4.0 / alpha).exp());
+        let alive = sigmoid(m, 0.5, self.alpha_m);
+        let mix = |dead: f64, live: f64| dead * (1.0 - alive) + live * alive;
+        let (low, high) = (
+            mix(self.birth.0, self.death.0),
+            mix(self.birth.1, self.death.1),
+        );
+        sigmoid(n, low, self.alpha_n) * (1.0 - sigmoid(n, high, self.alpha_n))
+    }
+}
+
+/// A rule over cells that take any value from 0 to 1.
+///
+/// Rules are written as `key=value` pairs between commas or semicolons, Lenia
+/// with `R`, `T`, `m` and `s` (Orbium is `R=13,T=10,m=0.15,s=0.015`) and
+/// SmoothLife with `ri`, `ra`, `b1`, `b2`, `d1`, `d2`, `sn`, `sm` and `dt`,
+/// each missing key keeping its value from Orbium or Rafler's paper. `Lenia`
+/// and `SmoothLife` name those defaults.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContinuousRule {
+    Lenia(Lenia),
+    SmoothLife(SmoothLife),
+}
+
+impl fmt::Display for ContinuousRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContinuousRule::Lenia(lenia) => write!(
+                f,
+                "R={},T={},m={},s={}",
+                lenia.radius, lenia.time, lenia.mu, lenia.sigma
+            ),
+            ContinuousRule::SmoothLife(smooth) => write!(
+                f,
+                "ri={},ra={},b1={},b2={},d1={},d2={},sn={},sm={},dt={}",
+                smooth.inner,
+                smooth.outer,
+                smooth.birth.0,
+                smooth.birth.1,
+                smooth.death.0,
+                smooth.death.1,
+                smooth.alpha_n,
+                smooth.alpha_m,
+                smooth.dt
+            ),
+        }
+    }
+}
+
+/// The largest radius a rule may reach out to, as for Larger-than-Life.
+const MAX_RADIUS: f64 = 500.0;
+
+const LENIA_KEYS: [&str; 4] = ["R", "T", "m", "s"];
+const SMOOTHLIFE_KEYS: [&str; 9] = ["ri", "ra", "b1", "b2", "d1", "d2", "sn", "sm", "dt"];
+
+impl FromStr for ContinuousRule {
+    type Err = ParseRuleError;
+
+    fn from_str(rule: &str) -> Result<ContinuousRule, ParseRuleError> {
+        let s = rule.trim();
+        if s.eq_ignore_ascii_case("Lenia") || s.eq_ignore_ascii_case("Orbium") {
+            return Ok(ContinuousRule::Lenia(Lenia::ORBIUM));
+        }
+        if s.eq_ignore_ascii_case("SmoothLife") {
+            return Ok(ContinuousRule::SmoothLife(SmoothLife::default()));
+        }
+        let pairs: Vec<(&str, f64)> = s
+            .split([',', ';'])
+            .map(|pair| {
+                let (key, value) = pair.split_once('=')?;
+                Some((key.trim(), value.trim().parse().ok()?))
+            })
+            .collect::<Option<_>>()
+            .ok_or_else(|| ParseRuleError::new(rule, "expected key=value pairs"))?;
+        let is_lenia = pairs.iter().all(|(key, _)| LENIA_KEYS.contains(key));
+        let is_smooth = pairs.iter().all(|(key, _)| SMOOTHLIFE_KEYS.contains(key));
+        if !is_lenia && !is_smooth {
+            return Err(ParseRuleError::new(
+                rule,
+                "expected Lenia's R, T, m and s or SmoothLife's ri, ra, b1, b2, d1, d2, sn, sm and dt",
+            ));
+        }
+        if pairs
+            .iter()
+            .any(|&(_, value)| !value.is_finite() || value < 0.0)
+        {
+            return Err(ParseRuleError::new(
+                rule,
+                "values must be finite and cannot be negative",
+            ));
+        }
+        let rule = if is_lenia {
+            let mut lenia = Lenia::ORBIUM;
+            for (key, value) in pairs {
+                match key {
+                    "R" => lenia.radius = value,
+                    "T" => lenia.time = value,
+                    "m" => lenia.mu = value,
+                    _ => lenia.sigma = value,
+                }
+            }
+            if !(1.0..=MAX_RADIUS).contains(&lenia.radius) || lenia.time < 1.0 || lenia.sigma == 0.0
+            {
+                return Err(ParseRuleError::new(
+                    rule,
+                    "Lenia needs R from 1 to 500, T of at least 1 and s above 0",
+                ));
+            }
+            ContinuousRule::Lenia(lenia)
+        } else {
+            let mut smooth = SmoothLife::default();
+            for (key, value) in pairs {
+                match key {
+                    "ri" => smooth.inner = value,
+                    "ra" => smooth.outer = value,
+                    "b1" => smooth.birth.0 = value,
+                    "b2" => smooth.birth.1 = value,
+                    "d1" => smooth.death.0 = value,
+                    "d2" => smooth.death.1 = value,
+                    "sn" => smooth.alpha_n = value,
+                    "sm" => smooth.alpha_m = value,
+                    _ => smooth.dt = value,
+                }
+            }
+            if smooth.inner >= smooth.outer
+                || smooth.outer > MAX_RADIUS
+                || smooth.dt == 0.0
+                || smooth.dt > 1.0
+            {
+                return Err(ParseRuleError::new(
+                    rule,
+                    "SmoothLife needs ri below ra, ra of at most 500, and dt above 0 and at most 1",
+                ));
+            }
+            ContinuousRule::SmoothLife(smooth)
+        };
+        Ok(rule)
+    }
+}
+
+/// The colour a frontend draws a cell of `value`, from black through purple
+/// and orange to pale yellow.
+pub fn color_map(value: f64) -> Color {
+    const STOPS: [Color; 5] = [
+        [0.0, 0.0, 0.0, 1.0],
+        [0.25, 0.04, 0.43, 1.0],
+        [0.73, 0.21, 0.33, 1.0],
+        [0.98, 0.55, 0.04, 1.0],
+        [0.99, 1.0, 0.64, 1.0],
+    ];
+    let x = value.clamp(0.0, 1.0) as f32 * (STOPS.len() - 1) as f32;
+    let i = (x as usize).min(STOPS.len() - 2);
+    let f = x - i as f32;
+    let (a, b) = (STOPS[i], STOPS[i + 1]);
+    [
+        a[0] + (b[0] - a[0]) * f,
+        a[1] + (b[1] - a[1]) * f,
+        a[2] + (b[2] - a[2]) * f,
+        1.0,
+    ]
+}
+
+type Complex = (f64, f64);
+
+fn mul(a: Complex, b: Complex) -> Complex {
+    (a.0 * b.0 - a.1 * b.1, a.0 * b.1 + a.1 * b.0)
+}
+
+/// In-place radix-2 FFT of `buf`, whose length is a power of two; the
+/// inverse is left unscaled.
+fn fft(buf: &mut [Complex], inverse: bool) {
+    let n = buf.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            buf.swap(i, j);
+        }
+    }
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let angle = sign * 2.0 * PI / len as f64;
+        let step = (angle.cos(), angle.sin());
+        for start in (0..n).step_by(len) {
+            let mut w = (1.0, 0.0);
+            for k in 0..len / 2 {
+                let a = buf[start + k];
+                let b = mul(buf[start + k + len / 2], w);
+                buf[start + k] = (a.0 + b.0, a.1 + b.1);
+                buf[start + k + len / 2] = (a.0 - b.0, a.1 - b.1);
+                w = mul(w, step);
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// FFT of `buf`, stored as rows of `width`, along both axes. The inverse is
+/// scaled back.
+fn fft2(buf: &mut [Complex], width: usize, inverse: bool) {
+    let height = buf.len() / width;
+    for row in buf.chunks_mut(width) {
+        fft(row, inverse);
+    }
+    let mut column = vec![(0.0, 0.0); height];
+    for w in 0..width {
+        for h in 0..height {
+            column[h] = buf[h * width + w];
+        }
+        fft(&mut column, inverse);
+        for h in 0..height {
+            buf[h * width + w] = column[h];
+        }
+    }
+    if inverse {
+        let scale = 1.0 / buf.len() as f64;
+        for c in buf.iter_mut() {
+            *c = (c.0 * scale, c.1 * scale);
+        }
+    }
+}
+
+/// A neighbourhood as weights summing to 1 at offsets from the cell, and,
+/// when the board can be transformed, its spectrum.
+struct Kernel {
+    taps: Vec<(i64, i64, f64)>,
+    spectrum: Option<Vec<Complex>>,
+}
+
+impl Kernel {
+    /// The kernel reaching out to `radius` with the weight `weight(distance)`.
+    fn new(radius: f64, weight: impl Fn(f64) -> f64) -> Kernel {
+        let reach = radius.ceil() as i64 + 1;
+        let mut taps = Vec::new();
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                let w = weight(((dx * dx + dy * dy) as f64).sqrt());
+                if w > 0.0 {
+                    taps.push((dx, dy, w));
+                }
+            }
+        }
+        let total: f64 = taps.iter().map(|&(_, _, w)| w).sum();
+        for tap in &mut taps {
+            tap.2 /= total;
+        }
+        Kernel {
+            taps,
+            spectrum: None,
+        }
+    }
+
+    fn transform(&mut self, width: usize, height: usize) {
+        let mut buf = vec![(0.0, 0.0); width * height];
+        for &(dx, dy, w) in &self.taps {
+            let x = dx.rem_euclid(width as i64) as usize;
+            let y = dy.rem_euclid(height as i64) as usize;
+            buf[y * width + x].0 += w;
+        }
+        fft2(&mut buf, width, false);
+        self.spectrum = Some(buf);
+    }
+}
+
+/// The share of a cell at `distance` lying inside a disc of `radius`, with
+/// the disc's edge smoothed over one cell.
+fn disc(radius: f64, distance: f64) -> f64 {
+    (radius + 0.5 - distance).clamp(0.0, 1.0)
+}
+
+/// A board of cells valued from 0 to 1 stepped under a `ContinuousRule`,
+/// with its edges joined as a torus.
+///
+/// Neighbourhoods are summed by convolution: through the FFT when both sides
+/// of the board are powers of two, and cell by cell otherwise, which gives
+/// the same values but slows down with the square of the radius.
+///
+/// As an `Engine`, cells from one half up count as alive and the state of a
+/// cell is its value in 255ths, which is as finely as the history keeps it.
+pub struct ContinuousLife {
+    width: usize,
+    height: usize,
+    rule: ContinuousRule,
+    kernels: Vec<Kernel>,
+    cells: Vec<f64>,
+    now: u64,
+    threads: usize,
+}
+
+impl ContinuousLife {
+    pub fn new(width: usize, height: usize, rule: ContinuousRule) -> ContinuousLife {
+        let mut kernels = match rule {
+            ContinuousRule::Lenia(lenia) => vec![Kernel::new(lenia.radius, |d| {
+                let r = d / lenia.radius;
+                if r > 0.0 && r < 1.0 {
+                    (4.0 - 1.0 / (r * (1.0 - r))).exp()
+                } else {
+                    0.0
+                }
+            })],
+            ContinuousRule::SmoothLife(smooth) => vec![
+                Kernel::new(smooth.inner, |d| disc(smooth.inner, d)),
+                Kernel::new(smooth.outer, |d| {
+                    disc(smooth.outer, d) * (1.0 - disc(smooth.inner, d))
+                }),
+            ],
+        };
+        if width.is_power_of_two() && height.is_power_of_two() {
+            for kernel in &mut kernels {
+                kernel.transform(width, height);
+            }
+        }
+        ContinuousLife {
+            width,
+            height,
+            rule,
+            kernels,
+            cells: vec![0.0; width * height],
+            now: 0,
+            threads: 1,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn rule(&self) -> ContinuousRule {
+        self.rule
+    }
+
+    /// Splits the cell-by-cell sums into bands of rows stepped on `threads`
+    /// threads. The result is the same for any number of threads.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    fn index(&self, x: i64, y: i64) -> usize {
+        let w = x.rem_euclid(self.width as i64) as usize;
+        let h = y.rem_euclid(self.height as i64) as usize;
+        h * self.width + w
+    }
+
+    pub fn value(&self, x: i64, y: i64) -> f64 {
+        self.cells[self.index(x, y)]
+    }
+
+    /// Sets a cell, clamped to 0 to 1.
+    pub fn set_value(&mut self, x: i64, y: i64, value: f64) {
+        let i = self.index(x, y);
+        self.cells[i] = value.clamp(0.0, 1.0);
+    }
+
+    /// The sum of every cell's value, which Lenia's creatures roughly keep.
+    pub fn mass(&self) -> f64 {
+        self.cells.iter().sum()
+    }
+
+    /// Each kernel's weighted sum round every cell.
+    fn neighbourhoods(&self) -> Vec<Vec<f64>> {
+        if self.kernels.iter().all(|kernel| kernel.spectrum.is_some()) {
+            self.convolve_fft()
+        } else {
+            self.kernels
+                .iter()
+                .map(|kernel| self.convolve_direct(kernel))
+                .collect()
+        }
+    }
+
+    fn convolve_fft(&self) -> Vec<Vec<f64>> {
+        let mut cells: Vec<Complex> = self.cells.iter().map(|&v| (v, 0.0)).collect();
+        fft2(&mut cells, self.width, false);
+        self.kernels
+            .iter()
+            .map(|kernel| {
+                let spectrum = kernel.spectrum.as_ref().unwrap();
+                // The kernels are symmetric, so convolving is the same as
+                // summing the weighted neighbours.
+                let mut product: Vec<Complex> = cells
+                    .iter()
+                    .zip(spectrum)
+                    .map(|(&a, &b)| mul(a, b))
+                    .collect();
+                fft2(&mut product, self.width, true);
+                product.into_iter().map(|c| c.0).collect()
+            })
+            .collect()
+    }
+
+    fn convolve_direct(&self, kernel: &Kernel) -> Vec<f64> {
+        let mut out = vec![0.0; self.cells.len()];
+        for_each_band(&mut out, self.width, self.threads, |first, rows| {
+            for (h, row) in (first..).zip(rows.chunks_mut(self.width)) {
+                for (w, sum) in row.iter_mut().enumerate() {
+                    *sum = kernel
+                        .taps
+                        .iter()
+                        .map(|&(dx, dy, weight)| weight * self.value(w as i64 + dx, h as i64 + dy))
+                        .sum();
+                }
+            }
+        });
+        out
+    }
+}
+
+impl Engine for ContinuousLife {
+    fn tick(&mut self) {
+        let sums = self.neighbourhoods();
+        match self.rule {
+            ContinuousRule::Lenia(lenia) => {
+                for (cell, &u) in self.cells.iter_mut().zip(&sums[0]) {
+                    let d = (u - lenia.mu) / lenia.sigma;
+                    let growth = 2.0 * (-d * d / 2.0).exp() - 1.0;
+                    *cell = (*cell + growth / lenia.time).clamp(0.0, 1.0);
+                }
+            }
+            ContinuousRule::SmoothLife(smooth) => {
+                for (i, cell) in self.cells.iter_mut().enumerate() {
+                    let s = smooth.transition(sums[1][i], sums[0][i]);
+                    *cell = if smooth.dt >= 1.0 {
+                        s
+                    } else {
+                        (*cell + smooth.dt * (2.0 * s - 1.0)).clamp(0.0, 1.0)
+                    };
+                }
+            }
+        }
+        self.now += 1;
+    }
+
+    fn generation(&self) -> u64 {
+        self.now
+    }
+
+    fn set_generation(&mut self, generation: u64) {
+        self.now = generation;
+    }
+
+    fn population(&self) -> u64 {
+        self.cells.iter().filter(|&&v| v >= 0.5).count() as u64
+    }
+
+    fn get(&self, x: i64, y: i64) -> bool {
+        self.value(x, y) >= 0.5
+    }
+
+    fn state(&self, x: i64, y: i64) -> u8 {
+        (self.value(x, y) * 255.0).round() as u8
+    }
+
+    fn set(&mut self, x: i64, y: i64, alive: bool) {
+        self.set_value(x, y, alive as u8 as f64);
+    }
+
+    fn set_state(&mut self, x: i64, y: i64, state: u8) {
+        self.set_value(x, y, state as f64 / 255.0);
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64, u8)> {
+        let mut out = Vec::new();
+        for h in 0..self.height as i64 {
+            for w in 0..self.width as i64 {
+                let state = self.state(w, h);
+                if state > 0 {
+                    out.push((w, h, state));
+                }
+            }
+        }
+        out
+    }
+
+    fn memory_usage(&self) -> usize {
+        let kernels: usize = self
+            .kernels
+            .iter()
+            .map(|kernel| {
+                kernel.taps.len() * size_of::<(i64, i64, f64)>()
+                    + kernel.spectrum.as_ref().map_or(0, Vec::len) * size_of::<Complex>()
+            })
+            .sum();
+        self.cells.len() * size_of::<f64>() + kernels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::Rng;
+
+    fn soup(life: &mut ContinuousLife, seed: u64) {
+        let mut rng = Rng::new(seed);
+        for h in 0..life.height() as i64 {
+            for w in 0..life.width() as i64 {
+                life.set_value(w, h, rng.gen_f64());
+            }
+        }
+    }
+
+    #[test]
+    fn parses_lenia_and_smoothlife() {
+        assert_eq!(
+            "R=13;T=10;m=0.15;s=0.015"
+                .parse::<ContinuousRule>()
+                .unwrap(),
+            ContinuousRule::Lenia(Lenia::ORBIUM)
+        );
+        assert_eq!(
+            "lenia".parse::<ContinuousRule>().unwrap().to_string(),
+            "R=13,T=10,m=0.15,s=0.015"
+        );
+        let smooth: ContinuousRule = "ra=12, ri=4, dt=0.1".parse().unwrap();
+        assert_eq!(
+            smooth.to_string().parse::<ContinuousRule>().unwrap(),
+            smooth
+        );
+        let ContinuousRule::SmoothLife(smooth) = smooth else {
+            panic!("{:?}", smooth)
+        };
+        assert_eq!((smooth.inner, smooth.outer, smooth.dt), (4.0, 12.0, 0.1));
+        assert_eq!(smooth.birth, SmoothLife::default().birth);
+        for bad in [
+            "",
+            "R=13,ra=21",
+            "R=0",
+            "R=inf",
+            "R=100000",
+            "m=x",
+            "m=NaN",
+            "ri=21,ra=7",
+            "ri=7,ra=501",
+            "dt=2",
+            "B3/S23",
+        ] {
+            assert!(bad.parse::<ContinuousRule>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn fft_and_direct_sums_agree() {
+        for rule in ["Lenia", "R=5,m=0.2", "SmoothLife", "ri=3,ra=9"] {
+            let mut life = ContinuousLife::new(32, 64, rule.parse().unwrap());
+            soup(&mut life, 3);
+            let by_fft = life.convolve_fft();
+            for (kernel, by_fft) in life.kernels.iter().zip(by_fft) {
+                let direct = life.convolve_direct(kernel);
+                let error = direct
+                    .iter()
+                    .zip(&by_fft)
+                    .map(|(a, b)| (a - b).abs())
+                    .fold(0.0, f64::max);
+                assert!(error < 1e-9, "{}: {}", rule, error);
+            }
+        }
+    }
+
+    #[test]
+    fn lenia_grows_a_field_at_mu() {
+        // Every kernel sums to 1, so a uniform field at `mu` sees exactly `mu`
+        // round each cell and grows by the most a generation can give.
+        for (width, height) in [(64, 32), (40, 30)] {
+            let mut life = ContinuousLife::new(width, height, "Lenia".parse().unwrap());
+            for h in 0..height as i64 {
+                for w in 0..width as i64 {
+                    life.set_value(w, h, 0.15);
+                }
+            }
+            life.tick();
+            assert!(life.cells.iter().all(|&v| (v - 0.25).abs() < 1e-9));
+            // An empty board can only shrink, so it stays empty.
+            let mut empty = ContinuousLife::new(width, height, "Lenia".parse().unwrap());
+            empty.step(3);
+            assert_eq!(empty.mass(), 0.0);
+        }
+    }
+
+    #[test]
+    fn threads_make_no_difference() {
+        let run = |threads| {
+            let mut life = ContinuousLife::new(30, 30, "ri=3,ra=9,dt=0.2".parse().unwrap());
+            life.set_threads(threads);
+            soup(&mut life, 8);
+            life.step(4);
+            life.cells
+        };
+        let cells = run(1);
+        assert!(cells.iter().all(|v| (0.0..=1.0).contains(v)));
+        assert_eq!(run(4), cells);
+    }
+}
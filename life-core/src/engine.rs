@@ -0,0 +1,59 @@
+use crate::noise::Region;
+
+/// The interface every stepping engine offers to the frontends.
+///
+/// Coordinates are signed so that engines without edges can be addressed the
+/// same way as finite boards; cells off a board with dead edges read as dead
+/// and ignore writes.
+pub trait Engine {
+    /// Advances one generation.
+    fn tick(&mut self);
+
+    /// Advances `generations` generations. Engines that can jump ahead faster
+    /// than one tick at a time override this.
+    fn step(&mut self, generations: u64) {
+        for _ in 0..generations {
+            self.tick();
+        }
+    }
+
+    /// Number of generations since the engine was created.
+    fn generation(&self) -> u64;
+
+    /// Sets the generation count, for an engine put back to a generation it
+    /// went through before.
+    fn set_generation(&mut self, generation: u64);
+
+    /// Number of live cells.
+    fn population(&self) -> u64;
+
+    fn get(&self, x: i64, y: i64) -> bool;
+
+    /// The Golly state number of a cell: 0 dead, 1 alive and, under a
+    /// Generations rule, 2 and up for dying. Two-state engines need not
+    /// override this.
+    fn state(&self, x: i64, y: i64) -> u8 {
+        self.get(x, y) as u8
+    }
+
+    fn set(&mut self, x: i64, y: i64, alive: bool);
+
+    /// Puts a cell in a Golly state number. Two-state engines make any state
+    /// but 0 alive.
+    fn set_state(&mut self, x: i64, y: i64, state: u8) {
+        self.set(x, y, state != 0)
+    }
+
+    /// Every cell in a state other than 0, with its state, in no particular
+    /// order.
+    fn live_cells(&self) -> Vec<(i64, i64, u8)>;
+
+    /// Approximate number of bytes the engine holds on the heap.
+    fn memory_usage(&self) -> usize;
+
+    /// The parts of the board the next tick recomputes, for engines that
+    /// leave quiet parts alone, so a frontend can show them.
+    fn active_tiles(&self) -> Option<Vec<Region>> {
+        None
+    }
+}
@@ -0,0 +1,490 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::engine::Engine;
+use crate::life3d::{parse_counts, write_counts};
+use crate::random::Rng;
+use crate::rule::ParseRuleError;
+
+/// The most live neighbours a `GraphRule` can ask for.
+const MAX_COUNT: u8 = 31;
+
+/// A totalistic rule for the nodes of a graph: a dead node is born with a
+/// number of live neighbours in the birth set, and a live one survives with a
+/// number in the survival set, whatever the degree of the node.
+///
+/// Rules are written in B/S notation, e.g. `B3/S23`, with commas and `a..b`
+/// ranges between counts above 9, as in `B3/S2..4,12`. Counts go up to 31;
+/// nodes with more live neighbours than that die or stay dead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GraphRule {
+    /// Bit `n` is set when a dead node with `n` live neighbours is born.
+    birth: u32,
+    /// Bit `n` is set when a live node with `n` live neighbours survives.
+    survival: u32,
+}
+
+impl Default for GraphRule {
+    fn default() -> GraphRule {
+        GraphRule::new(&[3], &[2, 3])
+    }
+}
+
+impl GraphRule {
+    /// Panics on a count above 31.
+    pub fn new(birth: &[u8], survival: &[u8]) -> GraphRule {
+        let bits = |counts: &[u8]| {
+            counts.iter().fold(0u32, |bits, &n| {
+                assert!(n <= MAX_COUNT, "counts go up to 31");
+                bits | 1 << n
+            })
+        };
+        GraphRule {
+            birth: bits(birth),
+            survival: bits(survival),
+        }
+    }
+
+    /// Whether a node is alive next generation, given whether it is alive
+    /// now and its count of live neighbours.
+    pub fn next(&self, alive: bool, count: usize) -> bool {
+        let bits = if alive { self.survival } else { self.birth };
+        count <= MAX_COUNT as usize && bits >> count & 1 == 1
+    }
+}
+
+impl fmt::Display for GraphRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "B")?;
+        write_counts(f, self.birth)?;
+        write!(f, "/S")?;
+        write_counts(f, self.survival)
+    }
+}
+
+impl FromStr for GraphRule {
+    type Err = ParseRuleError;
+
+    fn from_str(rule: &str) -> Result<GraphRule, ParseRuleError> {
+        let mut out = GraphRule::new(&[], &[]);
+        let too_many = "counts go up to 31";
+        for part in rule.trim().split('/') {
+            let (kind, counts) = part.split_at(part.chars().next().map_or(0, char::len_utf8));
+            match kind {
+                "B" | "b" => out.birth = parse_counts(rule, counts, MAX_COUNT, too_many)?,
+                "S" | "s" => out.survival = parse_counts(rule, counts, MAX_COUNT, too_many)?,
+                _ => return Err(ParseRuleError::new(rule, "expected B and S parts")),
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// An undirected graph without loops or repeated edges, with an adjacency
+/// list per node and, for some nodes, a position to draw them at.
+///
+/// Graphs are read from text, a line at a time: `a b` for an edge between
+/// nodes `a` and `b`, `a: b c d` for edges from `a` to each of the others,
+/// and `a @ x,y` for where to draw `a`. Nodes are numbered from 0, there are
+/// as many as the highest number mentioned plus one, and `#` starts a
+/// comment. Edge lists, adjacency lists and mixes of the two all read alike.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Graph {
+    neighbours: Vec<Vec<usize>>,
+    positions: Vec<Option<[f64; 2]>>,
+}
+
+impl Graph {
+    /// `nodes` nodes and no edges.
+    pub fn new(nodes: usize) -> Graph {
+        Graph {
+            neighbours: vec![Vec::new(); nodes],
+            positions: vec![None; nodes],
+        }
+    }
+
+    /// Watts and Strogatz's small world: a ring of `nodes` nodes each joined
+    /// to the `k` nearest on either side, with each edge moved to a random
+    /// far end with probability `p`.
+    pub fn small_world(nodes: usize, k: usize, p: f64, rng: &mut Rng) -> Graph {
+        let mut graph = Graph::new(nodes);
+        for a in 0..nodes {
+            for d in 1..=k.min(nodes.saturating_sub(1) / 2) {
+                let b = (a + d) % nodes;
+                if rng.gen_bool(p) {
+                    // Rewired, where there is a node left to rewire to.
+                    let free: Vec<usize> = (0..nodes)
+                        .filter(|&c| c != a && !graph.neighbours[a].contains(&c))
+                        .collect();
+                    if !free.is_empty() {
+                        graph.add_edge(a, free[rng.below(free.len() as u64) as usize]);
+                        continue;
+                    }
+                }
+                graph.add_edge(a, b);
+            }
+        }
+        graph
+    }
+
+    /// Barabási and Albert's scale-free graph: nodes are added one at a time,
+    /// each joined to `m` of the nodes before it picked in proportion to
+    /// their degree, starting from `m + 1` nodes all joined together.
+    pub fn scale_free(nodes: usize, m: usize, rng: &mut Rng) -> Graph {
+        let mut graph = Graph::new(nodes);
+        let start = (m + 1).min(nodes);
+        // Each node appears once per edge end, so a uniform pick from here is
+        // a pick in proportion to degree.
+        let mut ends = Vec::new();
+        for a in 0..start {
+            for b in a + 1..start {
+                graph.add_edge(a, b);
+                ends.extend([a, b]);
+            }
+        }
+        for a in start..nodes {
+            let mut targets = Vec::new();
+            while targets.len() < m {
+                let b = ends[rng.below(ends.len() as u64) as usize];
+                if !targets.contains(&b) {
+                    targets.push(b);
+                }
+            }
+            for b in targets {
+                graph.add_edge(a, b);
+                ends.extend([a, b]);
+            }
+        }
+        graph
+    }
+
+    /// Number of nodes.
+    pub fn len(&self) -> usize {
+        self.neighbours.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.neighbours.is_empty()
+    }
+
+    /// Adds nodes up to `node` if it is not yet in the graph.
+    fn reach(&mut self, node: usize) {
+        if node >= self.len() {
+            self.neighbours.resize(node + 1, Vec::new());
+            self.positions.resize(node + 1, None);
+        }
+    }
+
+    /// Joins `a` and `b`, adding either to the graph if need be. Loops and
+    /// edges already there are left out.
+    pub fn add_edge(&mut self, a: usize, b: usize) {
+        self.reach(a.max(b));
+        if a != b && !self.neighbours[a].contains(&b) {
+            self.neighbours[a].push(b);
+            self.neighbours[b].push(a);
+        }
+    }
+
+    pub fn neighbours(&self, node: usize) -> &[usize] {
+        &self.neighbours[node]
+    }
+
+    /// Every edge once, the lower node first.
+    pub fn edges(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.neighbours
+            .iter()
+            .enumerate()
+            .flat_map(|(a, ns)| ns.iter().filter(move |&&b| a < b).map(move |&b| (a, b)))
+    }
+
+    pub fn position(&self, node: usize) -> Option<[f64; 2]> {
+        self.positions[node]
+    }
+
+    pub fn set_position(&mut self, node: usize, position: [f64; 2]) {
+        self.reach(node);
+        self.positions[node] = Some(position);
+    }
+
+    /// Where to draw every node, fitted into the unit square.
+    ///
+    /// When every node has a position they are only scaled to fit. Otherwise
+    /// Fruchterman and Reingold's force layout runs for `iterations` rounds,
+    /// with edges pulling their ends together and every node pushing every
+    /// other away, starting the nodes without a position at random and
+    /// keeping those with one where they were put.
+    pub fn layout(&self, iterations: usize, rng: &mut Rng) -> Vec<[f64; 2]> {
+        let given: Vec<[f64; 2]> = self.positions.iter().flatten().copied().collect();
+        let mut positions = fit(&given);
+        if given.len() == self.len() {
+            return positions;
+        }
+        let mut given = positions.drain(..);
+        let mut positions: Vec<[f64; 2]> = self
+            .positions
+            .iter()
+            .map(|p| match p {
+                Some(_) => given.next().unwrap(),
+                None => [rng.gen_f64(), rng.gen_f64()],
+            })
+            .collect();
+        let k = (1.0 / self.len() as f64).sqrt();
+        for round in 0..iterations {
+            let mut moves = vec![[0.0; 2]; self.len()];
+            let mut push = |a: usize, b: usize, force: &dyn Fn(f64) -> f64| {
+                let d = [
+                    positions[a][0] - positions[b][0],
+                    positions[a][1] - positions[b][1],
+                ];
+                let distance = d[0].hypot(d[1]).max(1e-9);
+                let f = force(distance) / distance;
+                for i in 0..2 {
+                    moves[a][i] += d[i] * f;
+                    moves[b][i] -= d[i] * f;
+                }
+            };
+            for a in 0..self.len() {
+                for b in a + 1..self.len() {
+                    push(a, b, &|distance| k * k / distance);
+                }
+            }
+            for (a, b) in self.edges() {
+                push(a, b, &|distance| -distance * distance / k);
+            }
+            // The most a node moves cools from a tenth of the square to
+            // nothing.
+            let limit = 0.1 * (1.0 - round as f64 / iterations as f64);
+            for (node, m) in moves.iter().enumerate() {
+                if self.positions[node].is_some() {
+                    continue;
+                }
+                let length = m[0].hypot(m[1]).max(1e-9);
+                let step = length.min(limit) / length;
+                for i in 0..2 {
+                    positions[node][i] = (positions[node][i] + m[i] * step).clamp(0.0, 1.0);
+                }
+            }
+        }
+        if self.positions.iter().all(Option::is_none) {
+            positions = fit(&positions);
+        }
+        positions
+    }
+}
+
+/// Scales and moves `points` to fit the unit square, keeping their shape,
+/// and centres them in it.
+fn fit(points: &[[f64; 2]]) -> Vec<[f64; 2]> {
+    let low = points
+        .iter()
+        .fold([f64::MAX; 2], |l, p| [l[0].min(p[0]), l[1].min(p[1])]);
+    let high = points
+        .iter()
+        .fold([f64::MIN; 2], |h, p| [h[0].max(p[0]), h[1].max(p[1])]);
+    let size = (high[0] - low[0]).max(high[1] - low[1]);
+    let scale = if size > 0.0 { 1.0 / size } else { 0.0 };
+    let margin = [0, 1].map(|i| (1.0 - (high[i] - low[i]) * scale) / 2.0);
+    points
+        .iter()
+        .map(|p| [0, 1].map(|i| (p[i] - low[i]) * scale + margin[i]))
+        .collect()
+}
+
+impl FromStr for Graph {
+    type Err = ParseRuleError;
+
+    fn from_str(s: &str) -> Result<Graph, ParseRuleError> {
+        let mut graph = Graph::default();
+        for line in s.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let node = |n: &str| {
+                n.trim()
+                    .parse::<usize>()
+                    .map_err(|_| ParseRuleError::new(line, "nodes are numbered from 0"))
+            };
+            if let Some((a, at)) = line.split_once('@') {
+                let position = at
+                    .split_once(',')
+                    .and_then(|(x, y)| Some([x.trim().parse().ok()?, y.trim().parse().ok()?]))
+                    .ok_or_else(|| {
+                        ParseRuleError::new(line, "expected a position such as 0 @ 1.5,2")
+                    })?;
+                graph.set_position(node(a)?, position);
+            } else if let Some((a, bs)) = line.split_once(':') {
+                let a = node(a)?;
+                graph.reach(a);
+                for b in bs.split_whitespace() {
+                    graph.add_edge(a, node(b)?);
+                }
+            } else {
+                match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+                    &[a, b] => graph.add_edge(node(a)?, node(b)?),
+                    _ => return Err(ParseRuleError::new(line, "expected an edge such as 0 1")),
+                }
+            }
+        }
+        Ok(graph)
+    }
+}
+
+/// A `GraphRule` run on the nodes of a `Graph`.
+///
+/// To the `Engine` interface node `n` is the cell `(n, 0)`, and every other
+/// cell is dead, so histories work on graphs as they do on boards.
+pub struct GraphLife {
+    graph: Graph,
+    rule: GraphRule,
+    alive: Vec<bool>,
+    next: Vec<bool>,
+    now: u64,
+}
+
+impl GraphLife {
+    pub fn new(graph: Graph, rule: GraphRule) -> GraphLife {
+        GraphLife {
+            alive: vec![false; graph.len()],
+            next: vec![false; graph.len()],
+            graph,
+            rule,
+            now: 0,
+        }
+    }
+
+    pub fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    pub fn rule(&self) -> GraphRule {
+        self.rule
+    }
+
+    pub fn is_alive(&self, node: usize) -> bool {
+        self.alive[node]
+    }
+
+    pub fn set_alive(&mut self, node: usize, alive: bool) {
+        self.alive[node] = alive;
+    }
+
+    fn node(&self, x: i64, y: i64) -> Option<usize> {
+        (y == 0 && (0..self.alive.len() as i64).contains(&x)).then_some(x as usize)
+    }
+}
+
+impl Engine for GraphLife {
+    fn tick(&mut self) {
+        for (node, next) in self.next.iter_mut().enumerate() {
+            let count = self
+                .graph
+                .neighbours(node)
+                .iter()
+                .filter(|&&n| self.alive[n])
+                .count();
+            *next = self.rule.next(self.alive[node], count);
+        }
+        std::mem::swap(&mut self.alive, &mut self.next);
+        self.now += 1;
+    }
+
+    fn generation(&self) -> u64 {
+        self.now
+    }
+
+    fn set_generation(&mut self, generation: u64) {
+        self.now = generation;
+    }
+
+    fn population(&self) -> u64 {
+        self.alive.iter().filter(|&&alive| alive).count() as u64
+    }
+
+    fn get(&self, x: i64, y: i64) -> bool {
+        self.node(x, y).is_some_and(|node| self.alive[node])
+    }
+
+    fn set(&mut self, x: i64, y: i64, alive: bool) {
+        if let Some(node) = self.node(x, y) {
+            self.alive[node] = alive;
+        }
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64, u8)> {
+        (0..self.alive.len())
+            .filter(|&node| self.alive[node])
+            .map(|node| (node as i64, 0, 1))
+            .collect()
+    }
+
+    fn memory_usage(&self) -> usize {
+        let edges: usize = self.graph.neighbours.iter().map(Vec::len).sum();
+        self.alive.len() * 2 + edges * std::mem::size_of::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_edge_and_adjacency_lists() {
+        let graph: Graph =
+            "# a square with a tail\n0 1\n1 2\n2: 3 0 # and back\n3 4\n4 @ 2.5,-1\n6:"
+                .parse()
+                .unwrap();
+        assert_eq!(graph.len(), 7);
+        assert_eq!(graph.neighbours(2), [1, 3, 0]);
+        assert_eq!(
+            graph.edges().collect::<Vec<_>>(),
+            [(0, 1), (0, 2), (1, 2), (2, 3), (3, 4)]
+        );
+        assert_eq!(graph.position(4), Some([2.5, -1.0]));
+        assert!(graph.neighbours(6).is_empty());
+        for bad in ["0", "0 1 2", "a b", "0 @ 1", "-1 2"] {
+            assert!(bad.parse::<Graph>().is_err(), "{}", bad);
+        }
+
+        let rule: GraphRule = "B3/S2..4,12".parse().unwrap();
+        assert!(rule.next(true, 12) && !rule.next(true, 11) && rule.next(false, 3));
+        assert_eq!(rule.to_string(), "B3/S2,3,4,12");
+        assert!("B3,32/S".parse::<GraphRule>().is_err());
+    }
+
+    #[test]
+    fn signals_run_along_a_path() {
+        // Under B1/S a lone live node hands itself on to both sides, and the
+        // two signals run apart.
+        let mut graph = Graph::new(5);
+        for a in 0..4 {
+            graph.add_edge(a, a + 1);
+        }
+        let mut life = GraphLife::new(graph, "B1/S".parse().unwrap());
+        life.set(2, 0, true);
+        life.tick();
+        assert_eq!(life.live_cells(), [(1, 0, 1), (3, 0, 1)]);
+        life.tick();
+        assert_eq!(life.live_cells(), [(0, 0, 1), (4, 0, 1)]);
+    }
+
+    #[test]
+    fn generated_graphs_and_layouts() {
+        let mut rng = Rng::new(1);
+        let small = Graph::small_world(100, 2, 0.1, &mut rng);
+        // A rewired edge may land where another already is.
+        assert!((180..=200).contains(&small.edges().count()));
+        let free = Graph::scale_free(100, 2, &mut rng);
+        assert_eq!(free.edges().count(), 3 + 97 * 2);
+        assert!((0..100).all(|node| free.neighbours(node).len() >= 2));
+
+        let mut pinned = small.clone();
+        pinned.set_position(0, [0.5, 0.5]);
+        for graph in [&small, &pinned] {
+            let layout = graph.layout(50, &mut rng);
+            assert_eq!(layout.len(), 100);
+            assert!(layout.iter().flatten().all(|c| (0.0..=1.0).contains(c)));
+        }
+        assert_eq!(pinned.layout(50, &mut rng)[0], [0.5, 0.5]);
+    }
+}
@@ -0,0 +1,305 @@
+use crate::board::Live;
+use crate::engine::Engine;
+use crate::noise::Region;
+use crate::rule::Rule;
+use crate::universe::{Universe, TILE};
+
+/// Number of dead cells kept between the live cells and the edge, enough for
+/// nothing to be born off the board in the next tick.
+const MARGIN: i64 = 1;
+
+/// How often, in generations, a board allowed to shrink checks whether it
+/// could.
+const SHRINK_EVERY: u64 = 64;
+
+/// A dense `Universe` whose board grows wherever live cells come near its
+/// edge, so patterns run on as if the plane had no edges without paying for
+/// a sparse engine.
+///
+/// Cells keep their coordinates however the board grows: `origin` is where
+/// the board's top left corner lies, and moves up and left as the board grows
+/// that way. The board grows by at least a tile on a side, and by half its
+/// size once that is more, so a pattern growing steadily is copied only a
+/// few times. The origin only moves by even numbers of cells, which keeps the
+/// rows of hexagonal and the triangles of triangular rules as they were.
+///
+/// With `set_shrink`, the board also shrinks back round the live cells, with
+/// a tile to spare on each side, when they take up less than half of it
+/// either way.
+///
+/// Rules with B0 bring the whole margin to life every tick, so the board
+/// would double each tick until memory ran out. Run those on a fixed board.
+pub struct GrowingUniverse {
+    inner: Universe,
+    origin: (i64, i64),
+    threads: usize,
+    shrink: bool,
+}
+
+impl GrowingUniverse {
+    /// Starts with a `width` x `height` board at the origin.
+    pub fn new(width: usize, height: usize, rule: Rule) -> GrowingUniverse {
+        let mut inner = Universe::new(width, height);
+        inner.set_rule(rule);
+        GrowingUniverse {
+            inner,
+            origin: (0, 0),
+            threads: 1,
+            shrink: false,
+        }
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.inner.rule()
+    }
+
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads;
+        self.inner.set_threads(threads);
+    }
+
+    /// Lets the board shrink once its live cells leave most of it empty.
+    pub fn set_shrink(&mut self, shrink: bool) {
+        self.shrink = shrink;
+    }
+
+    /// Where the top left corner of the board is.
+    pub fn origin(&self) -> (i64, i64) {
+        self.origin
+    }
+
+    /// The board as it is now allocated.
+    pub fn size(&self) -> (usize, usize) {
+        (self.inner.width(), self.inner.height())
+    }
+
+    /// Grows the board, where need be, to hold the cells from `(x0, y0)` to
+    /// `(x1, y1)` and the margin round them.
+    fn reach(&mut self, x0: i64, y0: i64, x1: i64, y1: i64) {
+        let (width, height) = (self.inner.width() as i64, self.inner.height() as i64);
+        let (left, top) = self.origin;
+        let (right, bottom) = (left + width, top + height);
+        let extra = |size: i64| (size / 2).max(TILE as i64);
+        let grown = (
+            match x0 - MARGIN {
+                x if x < left => (x - extra(width)).div_euclid(2) * 2,
+                _ => left,
+            },
+            match y0 - MARGIN {
+                y if y < top => (y - extra(height)).div_euclid(2) * 2,
+                _ => top,
+            },
+            match x1 + MARGIN {
+                x if x >= right => x + 1 + extra(width),
+                _ => right,
+            },
+            match y1 + MARGIN {
+                y if y >= bottom => y + 1 + extra(height),
+                _ => bottom,
+            },
+        );
+        if grown != (left, top, right, bottom) {
+            self.resize(grown);
+        }
+    }
+
+    /// Grows the board on the sides where live cells touch its edge.
+    fn grow(&mut self) {
+        let board = self.inner.get_now_board();
+        let (width, height) = (board.width(), board.height());
+        if width == 0 || height == 0 {
+            return;
+        }
+        let left = (0..height).any(|h| board.is_alive(0, h));
+        let right = (0..height).any(|h| board.is_alive(width - 1, h));
+        let top = (0..width).any(|w| board.is_alive(w, 0));
+        let bottom = (0..width).any(|w| board.is_alive(w, height - 1));
+        let (x, y) = self.origin;
+        let (width, height) = (width as i64, height as i64);
+        self.reach(
+            x + !left as i64,
+            y + !top as i64,
+            x + width - 1 - !right as i64,
+            y + height - 1 - !bottom as i64,
+        );
+    }
+
+    /// Shrinks the board round its cells when they fill less than half of it
+    /// either way.
+    fn shrink(&mut self) {
+        let board = self.inner.get_now_board();
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+        for h in 0..board.height() {
+            for w in 0..board.width() {
+                if board.get(w, h).live != Live::Dead {
+                    let (x0, y0, x1, y1) = bounds.unwrap_or((w, h, w, h));
+                    bounds = Some((x0.min(w), y0.min(h), x1.max(w), y1.max(h)));
+                }
+            }
+        }
+        let Some((x0, y0, x1, y1)) = bounds else {
+            return;
+        };
+        let (x, y) = self.origin;
+        let spare = TILE as i64;
+        let shrunk = (
+            (x + x0 as i64 - spare).div_euclid(2) * 2,
+            (y + y0 as i64 - spare).div_euclid(2) * 2,
+            x + x1 as i64 + 1 + spare,
+            y + y1 as i64 + 1 + spare,
+        );
+        let (width, height) = (shrunk.2 - shrunk.0, shrunk.3 - shrunk.1);
+        if 2 * width < board.width() as i64 || 2 * height < board.height() as i64 {
+            self.resize(shrunk);
+        }
+    }
+
+    /// Moves the cells onto a board from `(left, top)` to just short of
+    /// `(right, bottom)`.
+    fn resize(&mut self, (left, top, right, bottom): (i64, i64, i64, i64)) {
+        let mut inner = Universe::new((right - left) as usize, (bottom - top) as usize);
+        inner.set_rule(self.inner.rule());
+        inner.set_threads(self.threads);
+        inner.set_generation(self.inner.generation());
+        let (dx, dy) = (self.origin.0 - left, self.origin.1 - top);
+        let old = self.inner.get_now_board();
+        let board = inner.get_now_board_mut();
+        for h in 0..old.height() {
+            for w in 0..old.width() {
+                let cell = old.get(w, h);
+                let (x, y) = (w as i64 + dx, h as i64 + dy);
+                if cell.live != Live::Dead
+                    && (0..board.width() as i64).contains(&x)
+                    && (0..board.height() as i64).contains(&y)
+                {
+                    board.set(x as usize, y as usize, *cell);
+                }
+            }
+        }
+        self.inner = inner;
+        self.origin = (left, top);
+    }
+}
+
+impl Engine for GrowingUniverse {
+    fn tick(&mut self) {
+        self.grow();
+        self.inner.tick();
+        if self.shrink && self.inner.generation().is_multiple_of(SHRINK_EVERY) {
+            self.shrink();
+        }
+    }
+
+    fn generation(&self) -> u64 {
+        self.inner.generation()
+    }
+
+    fn set_generation(&mut self, generation: u64) {
+        self.inner.set_generation(generation);
+    }
+
+    fn population(&self) -> u64 {
+        self.inner.population()
+    }
+
+    fn get(&self, x: i64, y: i64) -> bool {
+        self.inner.get(x - self.origin.0, y - self.origin.1)
+    }
+
+    fn state(&self, x: i64, y: i64) -> u8 {
+        self.inner.state(x - self.origin.0, y - self.origin.1)
+    }
+
+    /// Setting a cell off the board alive grows the board to hold it.
+    fn set(&mut self, x: i64, y: i64, alive: bool) {
+        self.set_state(x, y, alive as u8);
+    }
+
+    fn set_state(&mut self, x: i64, y: i64, state: u8) {
+        if state != 0 {
+            self.reach(x, y, x, y);
+        }
+        self.inner
+            .set_state(x - self.origin.0, y - self.origin.1, state);
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64, u8)> {
+        let (x, y) = self.origin;
+        let mut cells = self.inner.live_cells();
+        for cell in &mut cells {
+            cell.0 += x;
+            cell.1 += y;
+        }
+        cells
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.inner.memory_usage()
+    }
+
+    fn active_tiles(&self) -> Option<Vec<Region>> {
+        let (x, y) = self.origin;
+        let mut tiles = self.inner.active_tiles()?;
+        for tile in &mut tiles {
+            tile.x += x;
+            tile.y += y;
+        }
+        Some(tiles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sparse::SparseLife;
+
+    fn sorted(mut cells: Vec<(i64, i64, u8)>) -> Vec<(i64, i64, u8)> {
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn gliders_leave_the_first_board_either_way() {
+        let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+        let mut growing = GrowingUniverse::new(8, 8, Rule::CONWAY);
+        let mut sparse = SparseLife::new(Rule::CONWAY);
+        for &(x, y) in &glider {
+            // One heading down and right, and one up and left.
+            growing.set(x + 2, y + 2, true);
+            sparse.set(x + 2, y + 2, true);
+            growing.set(-x - 20, -y - 20, true);
+            sparse.set(-x - 20, -y - 20, true);
+        }
+        let origin = growing.origin();
+        assert!(origin.0 < -20 && origin.1 < -20);
+        assert_eq!(origin.0 % 2, 0);
+        for _ in 0..10 {
+            growing.step(20);
+            sparse.step(20);
+            assert_eq!(sorted(growing.live_cells()), sorted(sparse.live_cells()));
+        }
+        // Each glider went 50 cells, and the board followed.
+        assert!(growing.get(2 + 50, 4 + 50));
+        let (width, height) = growing.size();
+        assert!(width > 100 && height > 100);
+    }
+
+    #[test]
+    fn shrinks_round_what_is_left() {
+        let mut growing = GrowingUniverse::new(16, 16, Rule::CONWAY);
+        growing.set_shrink(true);
+        // A block, and far off a lone cell that dies at once.
+        for (x, y) in [(4, 4), (5, 4), (4, 5), (5, 5)] {
+            growing.set(x, y, true);
+        }
+        growing.set(500, 300, true);
+        assert!(growing.size().0 > 500);
+        growing.step(SHRINK_EVERY);
+        let (width, height) = growing.size();
+        assert!(width < 64 && height < 64, "{} x {}", width, height);
+        assert_eq!(
+            sorted(growing.live_cells()),
+            [(4, 4, 1), (4, 5, 1), (5, 4, 1), (5, 5, 1)]
+        );
+    }
+}
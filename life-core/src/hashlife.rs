@@ -0,0 +1,502 @@
+use std::collections::HashMap;
+use std::mem::size_of;
+
+use crate::engine::Engine;
+use crate::lattice::Lattice;
+use crate::rule::Rule;
+
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+/// A square of `2^level` x `2^level` cells. Nodes are hash-consed, so equal
+/// squares anywhere in the pattern, at any time, share one id.
+#[derive(Clone, Copy)]
+struct Node {
+    level: u8,
+    nw: NodeId,
+    ne: NodeId,
+    sw: NodeId,
+    se: NodeId,
+    population: u64,
+}
+
+/// Gosper's HashLife: a memoized quadtree that advances a pattern on an
+/// unbounded plane by `2^n` generations in time roughly proportional to the
+/// number of distinct squares it contains, not to the number of generations.
+///
+/// The root square of level `L` covers `-2^(L-1)..2^(L-1)` on both axes and is
+/// grown as the pattern spreads. Rules with B0 are not supported, since empty
+/// space must stay empty, and neither are Generations rules, non-totalistic
+/// rules or other lattices than the square one.
+pub struct HashLife {
+    nodes: Vec<Node>,
+    index: HashMap<[NodeId; 4], NodeId>,
+    /// Memoized `(node, j)` -> centre of `node` advanced `2^j` generations.
+    results: HashMap<(NodeId, u8), NodeId>,
+    /// Canonical empty node of each level.
+    empty: Vec<NodeId>,
+    root: NodeId,
+    generation: u64,
+    rule: Rule,
+    max_nodes: usize,
+}
+
+impl HashLife {
+    pub fn new(rule: Rule) -> HashLife {
+        assert!(!rule.is_born(0), "HashLife cannot run B0 rules");
+        assert_eq!(rule.states(), 2, "HashLife cannot run Generations rules");
+        assert!(
+            rule.is_totalistic(),
+            "HashLife cannot run non-totalistic rules"
+        );
+        assert_eq!(
+            rule.lattice(),
+            Lattice::Square,
+            "HashLife runs square lattices only"
+        );
+        let leaf = |population| Node {
+            level: 0,
+            nw: DEAD,
+            ne: DEAD,
+            sw: DEAD,
+            se: DEAD,
+            population,
+        };
+        let mut life = HashLife {
+            nodes: vec![leaf(0), leaf(1)],
+            index: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            root: DEAD,
+            generation: 0,
+            rule,
+            max_nodes: 1 << 22,
+        };
+        life.root = life.empty(3);
+        life
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    /// Number of distinct nodes currently stored.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Once more than `max_nodes` nodes are stored after a step, everything not
+    /// reachable from the current pattern is thrown away.
+    pub fn set_max_nodes(&mut self, max_nodes: usize) {
+        self.max_nodes = max_nodes;
+    }
+
+    fn level(&self) -> u8 {
+        self.nodes[self.root as usize].level
+    }
+
+    fn join(&mut self, nw: NodeId, ne: NodeId, sw: NodeId, se: NodeId) -> NodeId {
+        if let Some(&id) = self.index.get(&[nw, ne, sw, se]) {
+            return id;
+        }
+        let n = &self.nodes;
+        let population = n[nw as usize]
+            .population
+            .saturating_add(n[ne as usize].population)
+            .saturating_add(n[sw as usize].population)
+            .saturating_add(n[se as usize].population);
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node {
+            level: n[nw as usize].level + 1,
+            nw,
+            ne,
+            sw,
+            se,
+            population,
+        });
+        self.index.insert([nw, ne, sw, se], id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let e = *self.empty.last().unwrap();
+            let next = self.join(e, e, e, e);
+            self.empty.push(next);
+        }
+        self.empty[level as usize]
+    }
+
+    /// Doubles the root, keeping the pattern centred on the origin.
+    fn expand(&mut self) {
+        let root = self.nodes[self.root as usize];
+        let e = self.empty(root.level - 1);
+        let nw = self.join(e, e, e, root.nw);
+        let ne = self.join(e, e, root.ne, e);
+        let sw = self.join(e, root.sw, e, e);
+        let se = self.join(root.se, e, e, e);
+        self.root = self.join(nw, ne, sw, se);
+    }
+
+    /// Whether everything alive lies in the central quarter of the root.
+    fn is_padded(&self) -> bool {
+        let n = &self.nodes;
+        let root = n[self.root as usize];
+        let inner = |outer: NodeId, pick: fn(&Node) -> NodeId| {
+            let outer = n[outer as usize];
+            let middle = n[pick(&outer) as usize];
+            outer.population == n[pick(&middle) as usize].population
+        };
+        inner(root.nw, |q| q.se)
+            && inner(root.ne, |q| q.sw)
+            && inner(root.sw, |q| q.ne)
+            && inner(root.se, |q| q.nw)
+    }
+
+    /// Half the side of the root, i.e. the root covers `-half..half`.
+    fn half(&self) -> i64 {
+        1 << (self.level() - 1)
+    }
+
+    fn get_in(&self, id: NodeId, x: i64, y: i64) -> bool {
+        let node = &self.nodes[id as usize];
+        if node.level == 0 {
+            return id == ALIVE;
+        }
+        if node.population == 0 {
+            return false;
+        }
+        let half = 1 << (node.level - 1);
+        match (x >= half, y >= half) {
+            (false, false) => self.get_in(node.nw, x, y),
+            (true, false) => self.get_in(node.ne, x - half, y),
+            (false, true) => self.get_in(node.sw, x, y - half),
+            (true, true) => self.get_in(node.se, x - half, y - half),
+        }
+    }
+
+    fn set_in(&mut self, id: NodeId, x: i64, y: i64, alive: bool) -> NodeId {
+        let node = self.nodes[id as usize];
+        if node.level == 0 {
+            return if alive { ALIVE } else { DEAD };
+        }
+        let half = 1 << (node.level - 1);
+        let (mut nw, mut ne, mut sw, mut se) = (node.nw, node.ne, node.sw, node.se);
+        match (x >= half, y >= half) {
+            (false, false) => nw = self.set_in(nw, x, y, alive),
+            (true, false) => ne = self.set_in(ne, x - half, y, alive),
+            (false, true) => sw = self.set_in(sw, x, y - half, alive),
+            (true, true) => se = self.set_in(se, x - half, y - half, alive),
+        }
+        self.join(nw, ne, sw, se)
+    }
+
+    /// Adds the live cells of `id`, whose top left corner is `(x, y)`, to `out`.
+    fn collect(&self, id: NodeId, x: i64, y: i64, out: &mut Vec<(i64, i64, u8)>) {
+        let node = &self.nodes[id as usize];
+        if node.population == 0 {
+            return;
+        }
+        if node.level == 0 {
+            out.push((x, y, 1));
+            return;
+        }
+        let half = 1 << (node.level - 1);
+        self.collect(node.nw, x, y, out);
+        self.collect(node.ne, x + half, y, out);
+        self.collect(node.sw, x, y + half, out);
+        self.collect(node.se, x + half, y + half, out);
+    }
+
+    /// The level `k - 1` square at the centre of a level `k` node.
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let n = self.nodes[id as usize];
+        let (nw, ne, sw, se) = (
+            self.nodes[n.nw as usize],
+            self.nodes[n.ne as usize],
+            self.nodes[n.sw as usize],
+            self.nodes[n.se as usize],
+        );
+        self.join(nw.se, ne.sw, sw.ne, se.nw)
+    }
+
+    /// One generation of the centre 2x2 of a 4x4 node.
+    fn step_leaf(&mut self, id: NodeId) -> NodeId {
+        let mut bits = [[false; 4]; 4];
+        for (y, row) in bits.iter_mut().enumerate() {
+            for (x, bit) in row.iter_mut().enumerate() {
+                *bit = self.get_in(id, x as i64, y as i64);
+            }
+        }
+        let mut next = [DEAD; 4];
+        for (i, (x, y)) in [(1, 1), (2, 1), (1, 2), (2, 2)].into_iter().enumerate() {
+            let mut count = 0;
+            for dy in 0..3 {
+                for dx in 0..3 {
+                    if (dx, dy) != (1, 1) && bits[y + dy - 1][x + dx - 1] {
+                        count += 1;
+                    }
+                }
+            }
+            if self.rule.next(bits[y][x], count) {
+                next[i] = ALIVE;
+            }
+        }
+        self.join(next[0], next[1], next[2], next[3])
+    }
+
+    /// The level `k - 1` centre of a level `k` node, advanced `2^j` generations,
+    /// for `j <= k - 2`.
+    fn step_node(&mut self, id: NodeId, j: u8) -> NodeId {
+        let node = self.nodes[id as usize];
+        if node.population == 0 {
+            return self.empty(node.level - 1);
+        }
+        if let Some(&result) = self.results.get(&(id, j)) {
+            return result;
+        }
+        let result = if node.level == 2 {
+            self.step_leaf(id)
+        } else {
+            let (nw, ne, sw, se) = (
+                self.nodes[node.nw as usize],
+                self.nodes[node.ne as usize],
+                self.nodes[node.sw as usize],
+                self.nodes[node.se as usize],
+            );
+            // The nine overlapping level k - 1 squares covering the node.
+            let parts = [
+                node.nw,
+                self.join(nw.ne, ne.nw, nw.se, ne.sw),
+                node.ne,
+                self.join(nw.sw, nw.se, sw.nw, sw.ne),
+                self.join(nw.se, ne.sw, sw.ne, se.nw),
+                self.join(ne.sw, ne.se, se.nw, se.ne),
+                node.sw,
+                self.join(sw.ne, se.nw, sw.se, se.sw),
+                node.se,
+            ];
+            // At full speed both halves of the jump advance 2^(k-3) generations;
+            // slower jumps only move time forward in the second half.
+            let full = j == node.level - 2;
+            let mut c = [DEAD; 9];
+            for (c, part) in c.iter_mut().zip(parts) {
+                *c = if full {
+                    self.step_node(part, j - 1)
+                } else {
+                    self.centre(part)
+                };
+            }
+            let j = if full { j - 1 } else { j };
+            let quads = [
+                self.join(c[0], c[1], c[3], c[4]),
+                self.join(c[1], c[2], c[4], c[5]),
+                self.join(c[3], c[4], c[6], c[7]),
+                self.join(c[4], c[5], c[7], c[8]),
+            ];
+            let mut r = [DEAD; 4];
+            for (r, quad) in r.iter_mut().zip(quads) {
+                *r = self.step_node(quad, j);
+            }
+            self.join(r[0], r[1], r[2], r[3])
+        };
+        self.results.insert((id, j), result);
+        result
+    }
+
+    /// Advances the pattern by `2^n` generations in one jump.
+    pub fn step_pow2(&mut self, n: u8) {
+        assert!(n <= 56, "cannot jump more than 2^56 generations at once");
+        // The result is the centre half of the root, so the pattern has to
+        // fit there even after spreading at the speed of light for 2^n ticks.
+        // Keeping it in the central quarter of a root of level n + 3 or more
+        // leaves a margin of at least 2^n cells.
+        while self.level() < n + 3 || !self.is_padded() {
+            self.expand();
+        }
+        self.root = self.step_node(self.root, n);
+        self.generation += 1 << n;
+        if self.nodes.len() > self.max_nodes {
+            self.collect_garbage();
+        }
+    }
+
+    /// Drops every node that is not part of the current pattern, along with
+    /// the memoized results.
+    pub fn collect_garbage(&mut self) {
+        let leaves = self.nodes[..2].to_vec();
+        let old = std::mem::replace(&mut self.nodes, leaves);
+        self.index.clear();
+        self.results.clear();
+        self.empty = vec![DEAD];
+        let mut copied = HashMap::new();
+        self.root = self.copy_from(&old, self.root, &mut copied);
+    }
+
+    fn copy_from(
+        &mut self,
+        old: &[Node],
+        id: NodeId,
+        copied: &mut HashMap<NodeId, NodeId>,
+    ) -> NodeId {
+        if id == DEAD || id == ALIVE {
+            return id;
+        }
+        if let Some(&new) = copied.get(&id) {
+            return new;
+        }
+        let n = old[id as usize];
+        let nw = self.copy_from(old, n.nw, copied);
+        let ne = self.copy_from(old, n.ne, copied);
+        let sw = self.copy_from(old, n.sw, copied);
+        let se = self.copy_from(old, n.se, copied);
+        let new = self.join(nw, ne, sw, se);
+        copied.insert(id, new);
+        new
+    }
+}
+
+impl Engine for HashLife {
+    fn tick(&mut self) {
+        self.step_pow2(0)
+    }
+
+    /// Jumps by the powers of two in `generations`, those above `2^56` as
+    /// repeated jumps of `2^56`.
+    fn step(&mut self, generations: u64) {
+        for n in 0..56 {
+            if generations & 1 << n != 0 {
+                self.step_pow2(n);
+            }
+        }
+        for _ in 0..generations >> 56 {
+            self.step_pow2(56);
+        }
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+
+    fn population(&self) -> u64 {
+        self.nodes[self.root as usize].population
+    }
+
+    fn get(&self, x: i64, y: i64) -> bool {
+        let half = self.half();
+        if x < -half || x >= half || y < -half || y >= half {
+            return false;
+        }
+        self.get_in(self.root, x + half, y + half)
+    }
+
+    fn set(&mut self, x: i64, y: i64, alive: bool) {
+        while {
+            let half = self.half();
+            x < -half || x >= half || y < -half || y >= half
+        } {
+            self.expand();
+        }
+        let half = self.half();
+        self.root = self.set_in(self.root, x + half, y + half, alive);
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64, u8)> {
+        let mut out = Vec::new();
+        let half = self.half();
+        self.collect(self.root, -half, -half, &mut out);
+        out
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.nodes.capacity() * size_of::<Node>()
+            + self.index.capacity() * (size_of::<[NodeId; 4]>() + size_of::<NodeId>())
+            + self.results.capacity() * (size_of::<(NodeId, u8)>() + size_of::<NodeId>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::soup;
+    use crate::universe::Universe;
+
+    const GLIDER: [(i64, i64); 5] = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+
+    #[test]
+    fn glider_travels_far() {
+        let mut life = HashLife::new(Rule::CONWAY);
+        for (x, y) in GLIDER {
+            life.set(x, y, true);
+        }
+        life.step(1 << 20);
+        assert_eq!(life.generation(), 1 << 20);
+        assert_eq!(life.population(), 5);
+        let d = 1 << 18;
+        for (x, y) in GLIDER {
+            assert!(life.get(x + d, y + d));
+        }
+    }
+
+    #[test]
+    fn matches_dense_universe() {
+        let rule: Rule = "B36/S23".parse().unwrap();
+        let mut dense = Universe::new(160, 160);
+        dense.set_rule(rule);
+        let mut life = HashLife::new(rule);
+        soup(12345, 64..96, 64..96, &mut [&mut dense, &mut life]);
+        dense.step(45);
+        life.step(45);
+        for y in 0..160 {
+            for x in 0..160 {
+                assert_eq!(dense.get(x, y), life.get(x, y), "({}, {})", x, y);
+            }
+        }
+        assert_eq!(dense.population(), life.population());
+    }
+
+    #[test]
+    fn keeps_patterns_near_the_edges_of_the_root() {
+        // The first root covers -4..4, so each of these starts at or next to
+        // its edge, and the R-pentomino spreads well past it.
+        let blinker = [(-1, -2), (0, -2), (1, -2)];
+        let r_pentomino = [(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)];
+        let patterns: [&[(i64, i64)]; 4] = [
+            &blinker,
+            &blinker.map(|(x, y)| (x + 2, y - 2)),
+            &GLIDER.map(|(x, y)| (x - 4, y - 4)),
+            &r_pentomino.map(|(x, y)| (x + 1, y + 1)),
+        ];
+        for pattern in patterns {
+            let mut dense = Universe::new(128, 128);
+            let mut life = HashLife::new(Rule::CONWAY);
+            for &(x, y) in pattern {
+                dense.set(x + 64, y + 64, true);
+                life.set(x, y, true);
+            }
+            for generations in [1, 1, 2, 3, 8, 16] {
+                dense.step(generations);
+                life.step(generations);
+                for y in -64..64 {
+                    for x in -64..64 {
+                        assert_eq!(
+                            dense.get(x + 64, y + 64),
+                            life.get(x, y),
+                            "{:?} at ({}, {}) after {}",
+                            pattern,
+                            x,
+                            y,
+                            life.generation()
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Hensel's notation for isotropic non-totalistic rules, where a letter after
+//! a neighbour count picks out some arrangements of that many neighbours.
+//!
+//! Neighbourhoods are 9-bit numbers laid out as in Golly, from the top left
+//! down to the bottom right:
+//!
+//! ```text
+//! 256 128 64
+//!  32  16  8
+//!   4   2  1
+//! ```
+
+/// The bit of the cell itself.
+pub(crate) const CENTRE: u16 = 16;
+
+/// 512 bits, one per neighbourhood.
+pub(crate) type Neighborhoods = [u64; 8];
+
+/// The letters of each neighbour count up to 4, in Golly's order.
+const LETTERS: [&str; 5] = ["", "ce", "ceaikn", "ceaiknjqry", "ceaiknjqrytwz"];
+
+/// One arrangement for each letter of `LETTERS`.
+const CANONICAL: [&[u16]; 5] = [
+    &[],
+    &[1, 2],
+    &[5, 10, 3, 40, 33, 68],
+    &[69, 42, 11, 7, 98, 13, 14, 70, 41, 97],
+    &[325, 170, 15, 45, 99, 71, 106, 102, 43, 101, 105, 78, 108],
+];
+
+/// The bit of the cell at offset `(dx, dy)`.
+pub(crate) fn bit(dx: i32, dy: i32) -> u16 {
+    1 << ((1 - dy) * 3 + (1 - dx))
+}
+
+pub(crate) fn contains(set: &Neighborhoods, neighborhood: u16) -> bool {
+    set[neighborhood as usize / 64] >> (neighborhood % 64) & 1 == 1
+}
+
+pub(crate) fn insert(set: &mut Neighborhoods, neighborhood: u16) {
+    set[neighborhood as usize / 64] |= 1 << (neighborhood % 64);
+}
+
+/// The letters that can follow `count`; counts above 4 mirror `8 - count`.
+pub(crate) fn letters(count: u8) -> &'static str {
+    match count {
+        0..=4 => LETTERS[count as usize],
+        5..=8 => LETTERS[8 - count as usize],
+        _ => "",
+    }
+}
+
+/// Every rotation and reflection of `neighborhood`.
+fn symmetries(neighborhood: u16) -> [u16; 8] {
+    let mut out = [0; 8];
+    for (i, out) in out.iter_mut().enumerate() {
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if neighborhood & bit(dx, dy) == 0 {
+                    continue;
+                }
+                let (mut x, mut y) = if i >= 4 { (-dx, dy) } else { (dx, dy) };
+                for _ in 0..i % 4 {
+                    (x, y) = (-y, x);
+                }
+                *out |= bit(x, y);
+            }
+        }
+    }
+    out
+}
+
+/// The arrangement named by letter `i` of `letters(count)`.
+pub(crate) fn arrangement(count: u8, i: usize) -> u16 {
+    if count <= 4 {
+        CANONICAL[count as usize][i]
+    } else {
+        // Above 4 neighbours the arrangement is the inverse of the one below.
+        !CANONICAL[8 - count as usize][i] & 0x1ff & !CENTRE
+    }
+}
+
+/// Adds every arrangement of `count` live neighbours to `set`, or only those
+/// named by `letter`, with the cell itself alive or dead.
+pub(crate) fn insert_count(set: &mut Neighborhoods, count: u8, letter: Option<char>, alive: bool) {
+    let centre = if alive { CENTRE } else { 0 };
+    match letter.and_then(|letter| letters(count).find(letter)) {
+        Some(i) => {
+            for neighborhood in symmetries(arrangement(count, i)) {
+                insert(set, neighborhood | centre);
+            }
+        }
+        None => {
+            for neighborhood in 0..512u16 {
+                if neighborhood & CENTRE == 0 && neighborhood.count_ones() == count as u32 {
+                    insert(set, neighborhood | centre);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letters_split_each_count_into_classes() {
+        for count in 0..=8u8 {
+            let mut seen = [0u64; 8];
+            let letters: Vec<Option<char>> = match letters(count) {
+                "" => vec![None],
+                letters => letters.chars().map(Some).collect(),
+            };
+            for letter in letters {
+                let mut class = [0u64; 8];
+                insert_count(&mut class, count, letter, false);
+                for (seen, class) in seen.iter_mut().zip(class) {
+                    assert_eq!(*seen & class, 0, "{}{:?} overlaps", count, letter);
+                    *seen |= class;
+                }
+            }
+            let mut all = [0u64; 8];
+            insert_count(&mut all, count, None, false);
+            assert_eq!(seen, all, "count {}", count);
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::VecDeque;
+use std::mem::size_of;
+
+use crate::engine::Engine;
+
+/// A cell and its state, as given by `Engine::live_cells`.
+type Cell = (i64, i64, u8);
+
+/// Past generations of an engine, so a frontend can step backward, scrub to
+/// any generation it recorded and branch off from there.
+///
+/// Every `interval` recorded generations the live cells are kept in full; the
+/// ones in between keep only the cells that changed since the generation
+/// before. At most `capacity` generations are held, the oldest dropped first,
+/// a full snapshot and its deltas at a time.
+///
+/// Recording a generation at or before the last one recorded, as happens when
+/// an engine put back into the past is stepped again, drops everything
+/// recorded from that generation on: the new run is a branch that replaces
+/// the old future.
+pub struct History {
+    interval: usize,
+    capacity: usize,
+    frames: VecDeque<Frame>,
+    /// The cells of the last generation recorded, sorted, for the next one to
+    /// be compared against.
+    last: Vec<Cell>,
+}
+
+struct Frame {
+    generation: u64,
+    /// Every live cell, sorted.
+    cells: Vec<Cell>,
+    /// Each later generation, with the cells that changed since the one
+    /// before it; a state of 0 means the cell died.
+    deltas: Vec<(u64, Vec<Cell>)>,
+}
+
+/// The changes that turn `old` into `new`, both sorted.
+fn diff(old: &[Cell], new: &[Cell]) -> Vec<Cell> {
+    let mut out = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        let o = old.get(i).map(|&(x, y, _)| (y, x));
+        let n = new.get(j).map(|&(x, y, _)| (y, x));
+        match (o, n) {
+            (Some(o), Some(n)) if o == n => {
+                if old[i] != new[j] {
+                    out.push(new[j]);
+                }
+                i += 1;
+                j += 1;
+            }
+            (Some(o), n) if n.is_none_or(|n| o < n) => {
+                out.push((old[i].0, old[i].1, 0));
+                i += 1;
+            }
+            _ => {
+                out.push(new[j]);
+                j += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Applies `changes` to the sorted `cells`, keeping them sorted.
+fn apply(cells: &mut Vec<Cell>, changes: &[Cell]) {
+    for &(x, y, state) in changes {
+        match cells.binary_search_by_key(&(y, x), |&(x, y, _)| (y, x)) {
+            Ok(i) if state == 0 => {
+                cells.remove(i);
+            }
+            Ok(i) => cells[i].2 = state,
+            Err(i) if state != 0 => cells.insert(i, (x, y, state)),
+            Err(_) => {}
+        }
+    }
+}
+
+fn sorted(mut cells: Vec<Cell>) -> Vec<Cell> {
+    cells.sort_unstable_by_key(|&(x, y, _)| (y, x));
+    cells
+}
+
+impl History {
+    /// Keeps a full snapshot every `interval` generations recorded, and up to
+    /// `capacity` generations in all.
+    pub fn new(interval: usize, capacity: usize) -> History {
+        assert!(interval > 0, "snapshots need an interval of at least 1");
+        History {
+            interval,
+            capacity,
+            frames: VecDeque::new(),
+            last: Vec::new(),
+        }
+    }
+
+    /// Number of generations held.
+    pub fn len(&self) -> usize {
+        self.frames.iter().map(|frame| 1 + frame.deltas.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// The generations held, oldest first.
+    pub fn generations(&self) -> impl Iterator<Item = u64> + '_ {
+        self.frames.iter().flat_map(|frame| {
+            std::iter::once(frame.generation).chain(frame.deltas.iter().map(|&(g, _)| g))
+        })
+    }
+
+    /// The latest generation held before `generation`.
+    pub fn before(&self, generation: u64) -> Option<u64> {
+        self.generations().take_while(|&g| g < generation).last()
+    }
+
+    /// The earliest generation held after `generation`.
+    pub fn after(&self, generation: u64) -> Option<u64> {
+        self.generations().find(|&g| g > generation)
+    }
+
+    pub fn first(&self) -> Option<u64> {
+        self.frames.front().map(|frame| frame.generation)
+    }
+
+    pub fn last(&self) -> Option<u64> {
+        self.frames
+            .back()
+            .map(|frame| frame.deltas.last().map_or(frame.generation, |&(g, _)| g))
+    }
+
+    /// Records the engine's current generation.
+    pub fn record(&mut self, engine: &dyn Engine) {
+        let generation = engine.generation();
+        self.truncate(generation);
+        let cells = sorted(engine.live_cells());
+        match self.frames.back_mut() {
+            Some(frame) if frame.deltas.len() + 1 < self.interval => {
+                frame.deltas.push((generation, diff(&self.last, &cells)));
+            }
+            _ => self.frames.push_back(Frame {
+                generation,
+                cells: cells.clone(),
+                deltas: Vec::new(),
+            }),
+        }
+        self.last = cells;
+        while self.frames.len() > 1 && self.len() > self.capacity {
+            self.frames.pop_front();
+        }
+    }
+
+    /// Drops every generation from `generation` on.
+    fn truncate(&mut self, generation: u64) {
+        if self.last().is_none_or(|last| last < generation) {
+            return;
+        }
+        while self
+            .frames
+            .back()
+            .is_some_and(|frame| frame.generation >= generation)
+        {
+            self.frames.pop_back();
+        }
+        if let Some(frame) = self.frames.back_mut() {
+            frame.deltas.retain(|&(g, _)| g < generation);
+        }
+        self.last = self
+            .last()
+            .and_then(|last| self.cells_at(last))
+            .map_or_else(Vec::new, |(_, cells)| cells);
+    }
+
+    /// The latest generation held up to `generation`, with its live cells.
+    fn cells_at(&self, generation: u64) -> Option<(u64, Vec<Cell>)> {
+        let frame = self
+            .frames
+            .iter()
+            .rev()
+            .find(|frame| frame.generation <= generation)?;
+        let mut cells = frame.cells.clone();
+        let mut at = frame.generation;
+        for (g, changes) in frame.deltas.iter().take_while(|&&(g, _)| g <= generation) {
+            apply(&mut cells, changes);
+            at = *g;
+        }
+        Some((at, cells))
+    }
+
+    /// Puts `engine` back to the latest generation held up to `generation`,
+    /// and returns that generation. Nothing changes when none is held.
+    ///
+    /// Only cells are restored: colours and birth days of cells that change
+    /// are those of cells set by hand.
+    pub fn restore(&self, engine: &mut dyn Engine, generation: u64) -> Option<u64> {
+        let (at, cells) = self.cells_at(generation)?;
+        for (x, y, state) in diff(&sorted(engine.live_cells()), &cells) {
+            engine.set_state(x, y, state);
+        }
+        engine.set_generation(at);
+        Some(at)
+    }
+
+    /// Approximate number of bytes held.
+    pub fn memory_usage(&self) -> usize {
+        let cells: usize = self
+            .frames
+            .iter()
+            .map(|frame| {
+                frame.cells.capacity()
+                    + frame
+                        .deltas
+                        .iter()
+                        .map(|(_, changes)| changes.capacity())
+                        .sum::<usize>()
+            })
+            .sum();
+        (cells + self.last.capacity()) * size_of::<Cell>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::soup;
+    use crate::rule::Rule;
+    use crate::sparse::SparseLife;
+    use crate::universe::Universe;
+
+    #[test]
+    fn restores_every_generation_recorded() {
+        let mut universe = Universe::new(24, 24);
+        universe.set_rule("/2/3".parse::<Rule>().unwrap());
+        soup(99, 0..24, 0..24, &mut [&mut universe]);
+        let mut history = History::new(4, 100);
+        let mut past = Vec::new();
+        for _ in 0..30 {
+            history.record(&universe);
+            past.push(sorted(universe.live_cells()));
+            universe.tick();
+        }
+        assert_eq!(history.len(), 30);
+        assert_eq!((history.first(), history.last()), (Some(0), Some(29)));
+        for g in [29, 0, 13, 12, 7, 28] {
+            assert_eq!(history.restore(&mut universe, g), Some(g));
+            assert_eq!(universe.generation(), g);
+            assert_eq!(sorted(universe.live_cells()), past[g as usize], "{}", g);
+        }
+        // Stepping on from the past gives the same run again.
+        universe.tick();
+        assert_eq!(sorted(universe.live_cells()), past[29]);
+    }
+
+    #[test]
+    fn drops_the_oldest_and_branches() {
+        let mut life = SparseLife::new(Rule::CONWAY);
+        soup(99, 0..24, 0..24, &mut [&mut life]);
+        let mut history = History::new(5, 12);
+        for _ in 0..20 {
+            life.step(2);
+            history.record(&life);
+        }
+        // Whole frames of five go, leaving no more than twelve.
+        assert_eq!(history.len(), 10);
+        assert_eq!(history.first(), Some(22));
+        assert_eq!(history.before(30), Some(28));
+        assert_eq!(history.after(30), Some(32));
+        assert_eq!(history.restore(&mut life, 10), None);
+
+        assert_eq!(history.restore(&mut life, 31), Some(30));
+        for (x, y) in [(100, 100), (101, 100), (100, 101), (101, 101)] {
+            life.set(x, y, true);
+        }
+        life.tick();
+        history.record(&life);
+        assert_eq!(history.last(), Some(31));
+        assert_eq!(history.after(30), Some(31));
+        history.restore(&mut life, 24);
+        assert!(!life.get(100, 100));
+        history.restore(&mut life, 31);
+        assert!(life.get(100, 100));
+    }
+}
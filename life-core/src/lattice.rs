@@ -0,0 +1,206 @@
+use crate::board::DIRECTIONS;
+
+/// The shape of the cells and so which cells are neighbours.
+///
+/// Hexagonal and triangular lattices are laid out on the same rectangular
+/// grid of `(x, y)` cells as the square one, so every board and topology works
+/// with them. On the hexagonal lattice odd rows are shifted half a cell to the
+/// right; on the triangular one the cell at `(x, y)` points up when `x + y` is
+/// even. Either way a board whose edges are joined needs an even height (and a
+/// triangular one an even width) for the pattern of rows to carry on across.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Lattice {
+    #[default]
+    Square,
+    Hexagonal,
+    Triangular,
+}
+
+const HEX_EVEN_ROW: [[i32; 2]; 6] = [[-1, 0], [1, 0], [-1, -1], [0, -1], [-1, 1], [0, 1]];
+const HEX_ODD_ROW: [[i32; 2]; 6] = [[-1, 0], [1, 0], [0, -1], [1, -1], [0, 1], [1, 1]];
+
+/// The three edge neighbours and nine corner neighbours of an upward triangle.
+const TRIANGLE_UP: [[i32; 2]; 12] = [
+    [-1, -1],
+    [0, -1],
+    [1, -1],
+    [-2, 0],
+    [-1, 0],
+    [1, 0],
+    [2, 0],
+    [-2, 1],
+    [-1, 1],
+    [0, 1],
+    [1, 1],
+    [2, 1],
+];
+const TRIANGLE_DOWN: [[i32; 2]; 12] = [
+    [-2, -1],
+    [-1, -1],
+    [0, -1],
+    [1, -1],
+    [2, -1],
+    [-2, 0],
+    [-1, 0],
+    [1, 0],
+    [2, 0],
+    [-1, 1],
+    [0, 1],
+    [1, 1],
+];
+
+/// Side of the triangles, and width of the hexagons, when rows are one unit apart.
+const SIDE: f64 = 1.154_700_538_379_251_5;
+
+impl Lattice {
+    /// Number of neighbours of every cell.
+    pub fn neighbors(&self) -> u8 {
+        match self {
+            Lattice::Square => 8,
+            Lattice::Hexagonal => 6,
+            Lattice::Triangular => 12,
+        }
+    }
+
+    /// Offsets of the neighbours of the cell at `(x, y)`, which depend on the
+    /// row for hexagons and on which way the cell points for triangles.
+    pub fn offsets(&self, x: i64, y: i64) -> &'static [[i32; 2]] {
+        match self {
+            Lattice::Square => &DIRECTIONS,
+            Lattice::Hexagonal if y.rem_euclid(2) == 0 => &HEX_EVEN_ROW,
+            Lattice::Hexagonal => &HEX_ODD_ROW,
+            Lattice::Triangular if (x + y).rem_euclid(2) == 0 => &TRIANGLE_UP,
+            Lattice::Triangular => &TRIANGLE_DOWN,
+        }
+    }
+
+    /// How far apart neighbouring cells of a row are drawn, in units of the
+    /// distance between rows.
+    pub fn column_width(&self) -> f64 {
+        match self {
+            Lattice::Square => 1.0,
+            Lattice::Hexagonal => SIDE,
+            Lattice::Triangular => SIDE / 2.0,
+        }
+    }
+
+    /// The corners of the cell at `(x, y)` as drawn, in units of the
+    /// distance between rows, with row `y` spanning `y..y + 1`.
+    pub fn outline(&self, x: i64, y: i64) -> Vec<[f64; 2]> {
+        match self {
+            Lattice::Square => {
+                let (x, y) = (x as f64, y as f64);
+                vec![[x, y], [x + 1.0, y], [x + 1.0, y + 1.0], [x, y + 1.0]]
+            }
+            Lattice::Hexagonal => {
+                let [cx, cy] = self.hexagon_center(x, y);
+                // Pointy-top hexagons overlap the rows above and below by a third.
+                let (half, r) = (SIDE / 2.0, 2.0 / 3.0);
+                vec![
+                    [cx, cy - r],
+                    [cx + half, cy - r / 2.0],
+                    [cx + half, cy + r / 2.0],
+                    [cx, cy + r],
+                    [cx - half, cy + r / 2.0],
+                    [cx - half, cy - r / 2.0],
+                ]
+            }
+            Lattice::Triangular => {
+                let left = x as f64 * SIDE / 2.0;
+                let (top, bottom) = (y as f64, y as f64 + 1.0);
+                let (base, apex) = if (x + y).rem_euclid(2) == 0 {
+                    (bottom, top)
+                } else {
+                    (top, bottom)
+                };
+                vec![[left, base], [left + SIDE, base], [left + SIDE / 2.0, apex]]
+            }
+        }
+    }
+
+    fn hexagon_center(&self, x: i64, y: i64) -> [f64; 2] {
+        let shift = if y.rem_euclid(2) == 1 { 0.5 } else { 0.0 };
+        [(x as f64 + 0.5 + shift) * SIDE, y as f64 + 0.5]
+    }
+
+    /// The cell drawn at point `(px, py)`, in the units of `outline`.
+    pub fn cell_at(&self, px: f64, py: f64) -> (i64, i64) {
+        let y = py.floor() as i64;
+        match self {
+            Lattice::Square => (px.floor() as i64, y),
+            Lattice::Hexagonal => {
+                // The nearest centre wins; it lies in this row or the next one over.
+                let mut best = (0, 0, f64::MAX);
+                for y in y - 1..=y + 1 {
+                    let x = (px / SIDE).floor() as i64;
+                    for x in x - 1..=x + 1 {
+                        let [cx, cy] = self.hexagon_center(x, y);
+                        let d = (cx - px).powi(2) + (cy - py).powi(2);
+                        if d < best.2 {
+                            best = (x, y, d);
+                        }
+                    }
+                }
+                (best.0, best.1)
+            }
+            Lattice::Triangular => {
+                let v = py - y as f64;
+                let x = (px / (SIDE / 2.0)).floor() as i64;
+                // Of the two triangles overlapping this column, keep the one
+                // that is wide enough at this height.
+                let inside = |x: i64| {
+                    let u = px / (SIDE / 2.0) - x as f64 - 1.0;
+                    let up = (x + y).rem_euclid(2) == 0;
+                    u.abs() <= if up { v } else { 1.0 - v }
+                };
+                if inside(x) {
+                    (x, y)
+                } else {
+                    (x - 1, y)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neighbours_are_mutual() {
+        for lattice in [Lattice::Square, Lattice::Hexagonal, Lattice::Triangular] {
+            for (x, y) in [(0, 0), (1, 0), (0, 1), (3, 5), (-2, -7)] {
+                let offsets = lattice.offsets(x, y);
+                assert_eq!(offsets.len(), lattice.neighbors() as usize);
+                for &[dx, dy] in offsets {
+                    let (nx, ny) = (x + dx as i64, y + dy as i64);
+                    assert!(
+                        lattice.offsets(nx, ny).contains(&[-dx, -dy]),
+                        "{:?} ({}, {}) -> ({}, {})",
+                        lattice,
+                        x,
+                        y,
+                        nx,
+                        ny
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cells_contain_their_centres() {
+        for lattice in [Lattice::Square, Lattice::Hexagonal, Lattice::Triangular] {
+            for y in -3..3 {
+                for x in -3..3 {
+                    let outline = lattice.outline(x, y);
+                    let n = outline.len() as f64;
+                    let cx = outline.iter().map(|p| p[0]).sum::<f64>() / n;
+                    let cy = outline.iter().map(|p| p[1]).sum::<f64>() / n;
+                    assert_eq!(lattice.cell_at(cx, cy), (x, y), "{:?}", lattice);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! The Game of Life engine shared by every frontend in this workspace.
+//!
+//! The console, piston, kiss3d, wasm and birthday cake programs only draw; the
+//! rules and the board live here so they behave the same everywhere.
+
+mod bitboard;
+mod board;
+mod continuous;
+mod engine;
+mod graph;
+mod growing;
+mod hashlife;
+mod hensel;
+mod history;
+mod lattice;
+mod life1d;
+mod life3d;
+mod ltl;
+mod margolus;
+mod noise;
+mod parallel;
+mod penrose;
+mod random;
+mod rule;
+mod rulefile;
+mod sparse;
+mod topology;
+mod universe;
+
+pub use bitboard::BitBoard;
+pub use board::{Board, Cell, Color, Live, BLACK, DIRECTIONS};
+pub use continuous::{color_map, ContinuousLife, ContinuousRule, Lenia, SmoothLife};
+pub use engine::Engine;
+pub use graph::{Graph, GraphLife, GraphRule};
+pub use growing::GrowingUniverse;
+pub use hashlife::HashLife;
+pub use history::History;
+pub use lattice::Lattice;
+pub use life1d::{Rule1d, Universe1d};
+pub use life3d::{Rule3d, Universe3d};
+pub use ltl::{LargerThanLife, LtlRule, Neighborhood};
+pub use margolus::{BlockRule, Margolus};
+pub use noise::{Noise, Region};
+pub use penrose::{Penrose, PenroseKind, Tile, TileShape};
+pub use random::Rng;
+pub use rule::{ParseRuleError, Rule};
+pub use rulefile::{RuleFile, RuleLoader};
+pub use sparse::SparseLife;
+pub use topology::{Axis, Edge, Grid, Topology};
+pub use universe::{Universe, Update};
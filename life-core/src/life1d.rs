@@ -0,0 +1,331 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::engine::Engine;
+use crate::rule::ParseRuleError;
+
+/// A rule for a row of cells, each of which looks at the `range` cells on
+/// either side of it.
+///
+/// Rules are written as Wolfram codes: `110` or `W110` for the elementary
+/// rules, whose 8 bits give the next state of each neighbourhood of three
+/// cells read as a binary number with the leftmost cell highest, and
+/// `W<code>,R2` for the 32 neighbourhoods of range 2. Totalistic rules, which
+/// only count the live cells among the `2 * range + 1`, are written
+/// `T<code>,R<range>`, bit `n` of the code giving the next state for `n` live
+/// cells, so `T20,R2` is the rule with 2 or 4 live.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rule1d {
+    range: u8,
+    totalistic: bool,
+    code: u64,
+}
+
+impl Default for Rule1d {
+    fn default() -> Rule1d {
+        Rule1d::RULE_110
+    }
+}
+
+impl Rule1d {
+    /// Rule 110, which is Turing complete.
+    pub const RULE_110: Rule1d = Rule1d::elementary(110);
+
+    /// Rule 30, whose centre column Wolfram used as a random number generator.
+    pub const RULE_30: Rule1d = Rule1d::elementary(30);
+
+    /// Rule 90, which draws Sierpiński's triangle from a single cell.
+    pub const RULE_90: Rule1d = Rule1d::elementary(90);
+
+    pub const fn elementary(code: u8) -> Rule1d {
+        Rule1d {
+            range: 1,
+            totalistic: false,
+            code: code as u64,
+        }
+    }
+
+    /// Panics unless `range` is at least 1 and `code` has a bit for each of
+    /// the `2 * range + 2` counts and no more.
+    pub fn totalistic(range: u8, code: u64) -> Rule1d {
+        assert!(
+            (1..=31).contains(&range),
+            "totalistic ranges run from 1 to 31"
+        );
+        assert!(
+            range == 31 || code >> (2 * range + 2) == 0,
+            "the code has a bit for each count"
+        );
+        Rule1d {
+            range,
+            totalistic: true,
+            code,
+        }
+    }
+
+    pub fn range(&self) -> u8 {
+        self.range
+    }
+
+    pub fn is_totalistic(&self) -> bool {
+        self.totalistic
+    }
+
+    pub fn code(&self) -> u64 {
+        self.code
+    }
+
+    /// Whether a cell is alive next generation, given its neighbourhood from
+    /// left to right.
+    pub fn next(&self, neighbourhood: impl IntoIterator<Item = bool>) -> bool {
+        let index = if self.totalistic {
+            neighbourhood.into_iter().filter(|&alive| alive).count() as u64
+        } else {
+            neighbourhood
+                .into_iter()
+                .fold(0, |index, alive| index << 1 | alive as u64)
+        };
+        self.code >> index & 1 == 1
+    }
+}
+
+impl fmt::Display for Rule1d {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let letter = if self.totalistic { 'T' } else { 'W' };
+        write!(f, "{}{}", letter, self.code)?;
+        if self.totalistic || self.range != 1 {
+            write!(f, ",R{}", self.range)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Rule1d {
+    type Err = ParseRuleError;
+
+    fn from_str(rule: &str) -> Result<Rule1d, ParseRuleError> {
+        let invalid = || ParseRuleError::new(rule, "expected a code such as 110, W110 or T20,R2");
+        let mut code = None;
+        let mut totalistic = false;
+        let mut range = 1;
+        for term in rule.split(',').map(str::trim) {
+            let (letter, number) = match term.chars().next() {
+                Some(c) if c.is_ascii_alphabetic() => (c.to_ascii_uppercase(), &term[1..]),
+                _ => ('W', term),
+            };
+            let number: u64 = number.trim().parse().map_err(|_| invalid())?;
+            match letter {
+                'W' | 'T' if code.is_none() => {
+                    totalistic = letter == 'T';
+                    code = Some(number);
+                }
+                'R' => range = number,
+                _ => return Err(invalid()),
+            }
+        }
+        let code = code.ok_or_else(invalid)?;
+        let bits = if totalistic {
+            (1..=31)
+                .contains(&range)
+                .then_some(2 * range + 2)
+                .ok_or_else(|| ParseRuleError::new(rule, "totalistic ranges run from 1 to 31"))?
+        } else {
+            (1..=2)
+                .contains(&range)
+                .then_some(1 << (2 * range + 1))
+                .ok_or_else(|| ParseRuleError::new(rule, "Wolfram codes have a range of 1 or 2"))?
+        };
+        if bits < 64 && code >> bits != 0 {
+            return Err(ParseRuleError::new(
+                rule,
+                "the code has more bits than neighbourhoods",
+            ));
+        }
+        Ok(Rule1d {
+            range: range as u8,
+            totalistic,
+            code,
+        })
+    }
+}
+
+/// A ring of `width` cells stepped under a `Rule1d`, shown as a spacetime
+/// diagram `height` generations deep.
+///
+/// Row `y` of the board holds generation `y` until the board is full, after
+/// which every tick scrolls the rows up by one and the newest generation
+/// takes the bottom row, so time runs down the board. Cells are read and
+/// written in these coordinates, and `live_cells` reports the whole diagram,
+/// but only the newest row is stepped and `population` counts it alone.
+/// Edits to older rows are only for show.
+pub struct Universe1d {
+    width: usize,
+    height: usize,
+    rule: Rule1d,
+    cells: Vec<bool>,
+    now: u64,
+}
+
+impl Universe1d {
+    pub fn new(width: usize, height: usize, rule: Rule1d) -> Universe1d {
+        Universe1d {
+            width,
+            height,
+            rule,
+            cells: vec![false; width * height],
+            now: 0,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn rule(&self) -> Rule1d {
+        self.rule
+    }
+
+    /// The board row the newest generation is on.
+    pub fn current_row(&self) -> usize {
+        (self.now as usize).min(self.height.saturating_sub(1))
+    }
+
+    fn row(&self, y: usize) -> &[bool] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+}
+
+impl Engine for Universe1d {
+    fn tick(&mut self) {
+        if self.width == 0 || self.height == 0 {
+            self.now += 1;
+            return;
+        }
+        let range = self.rule.range as i64;
+        let width = self.width as i64;
+        let row = self.row(self.current_row());
+        let next: Vec<bool> = (0..width)
+            .map(|x| {
+                self.rule
+                    .next((x - range..=x + range).map(|x| row[x.rem_euclid(width) as usize]))
+            })
+            .collect();
+        let to = self.current_row() + 1;
+        let to = if to < self.height {
+            to
+        } else {
+            self.cells.copy_within(self.width.., 0);
+            to - 1
+        };
+        self.cells[to * self.width..(to + 1) * self.width].copy_from_slice(&next);
+        self.now += 1;
+    }
+
+    fn generation(&self) -> u64 {
+        self.now
+    }
+
+    fn set_generation(&mut self, generation: u64) {
+        self.now = generation;
+    }
+
+    fn population(&self) -> u64 {
+        if self.height == 0 {
+            return 0;
+        }
+        let row = self.row(self.current_row());
+        row.iter().filter(|&&alive| alive).count() as u64
+    }
+
+    /// Cells beyond either end of the row wrap round the ring.
+    fn get(&self, x: i64, y: i64) -> bool {
+        self.width > 0
+            && (0..self.height as i64).contains(&y)
+            && self.cells[y as usize * self.width + x.rem_euclid(self.width as i64) as usize]
+    }
+
+    fn set(&mut self, x: i64, y: i64, alive: bool) {
+        if self.width > 0 && (0..self.height as i64).contains(&y) {
+            self.cells[y as usize * self.width + x.rem_euclid(self.width as i64) as usize] = alive;
+        }
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64, u8)> {
+        let mut out = Vec::new();
+        for h in 0..self.height {
+            for w in 0..self.width {
+                if self.cells[h * self.width + w] {
+                    out.push((w as i64, h as i64, 1));
+                }
+            }
+        }
+        out
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.cells.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_wolfram_and_totalistic_codes() {
+        assert_eq!("110".parse::<Rule1d>().unwrap(), Rule1d::RULE_110);
+        assert_eq!("w30".parse::<Rule1d>().unwrap(), Rule1d::RULE_30);
+        assert_eq!(Rule1d::RULE_90.to_string(), "W90");
+        let wide: Rule1d = "W4294967295,R2".parse().unwrap();
+        assert_eq!(wide.range(), 2);
+        assert_eq!(wide.to_string(), "W4294967295,R2");
+        let totalistic: Rule1d = "R2,T20".parse().unwrap();
+        assert_eq!(totalistic, Rule1d::totalistic(2, 20));
+        assert_eq!(totalistic.to_string(), "T20,R2");
+        assert!(totalistic.next([true, false, true, false, false]));
+        assert!(!totalistic.next([true, true, true, false, false]));
+        for bad in [
+            "", "256", "W110,R3", "T64,R2", "T1,R0", "X110", "W1,T2", "B3/S23",
+        ] {
+            assert!(bad.parse::<Rule1d>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn rule_90_draws_sierpinski_down_the_board() {
+        let mut life = Universe1d::new(9, 4, Rule1d::RULE_90);
+        life.set(4, 0, true);
+        life.step(3);
+        let rows: Vec<String> = (0..4)
+            .map(|y| {
+                (0..9)
+                    .map(|x| if life.get(x, y) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(rows, ["....#....", "...#.#...", "..#...#..", ".#.#.#.#."]);
+        assert_eq!(life.population(), 4);
+        // Once the board is full it scrolls, keeping the newest row at the
+        // bottom.
+        life.tick();
+        assert_eq!(life.current_row(), 3);
+        assert!(life.get(3, 2) && life.get(0, 3) && life.get(8, 3));
+        assert_eq!(life.population(), 2);
+    }
+
+    #[test]
+    fn rule_110_grows_to_the_left() {
+        let mut life = Universe1d::new(16, 8, Rule1d::RULE_110);
+        life.set(15, 0, true);
+        life.step(7);
+        // The pattern reaches one cell further left each generation.
+        for y in 0..8 {
+            assert!(life.get(15 - y, y), "{}", y);
+            assert!((0..15 - y).all(|x| !life.get(x, y)), "{}", y);
+        }
+    }
+}
@@ -0,0 +1,369 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ltl::Neighborhood;
+use crate::rule::ParseRuleError;
+
+/// The 26 cells of a cube around a cell.
+const MOORE: [[i32; 3]; 26] = {
+    let mut out = [[0; 3]; 26];
+    let mut i = 0;
+    let mut n = 0;
+    while n < 27 {
+        let offset = [n % 3 - 1, n / 3 % 3 - 1, n / 9 - 1];
+        if n != 13 {
+            out[i] = offset;
+            i += 1;
+        }
+        n += 1;
+    }
+    out
+};
+
+/// The 6 cells sharing a face with a cell.
+const VON_NEUMANN: [[i32; 3]; 6] = [
+    [-1, 0, 0],
+    [1, 0, 0],
+    [0, -1, 0],
+    [0, 1, 0],
+    [0, 0, -1],
+    [0, 0, 1],
+];
+
+/// A totalistic rule in three dimensions, counting either the 26 neighbours
+/// of the cube around a cell or the 6 that share a face with it.
+///
+/// Rules are written in Bays' notation `EₗEᵤFₗFᵤ`, where a live cell survives
+/// with `Eₗ..=Eᵤ` neighbours and a dead one is born with `Fₗ..=Fᵤ`, so Life
+/// 4555 is `4555` (or `4/5/5/5` once counts pass 9); or as B/S sets, e.g.
+/// `B5/S45`, with commas and `a..b` ranges between counts above 9, as in
+/// `B14..19/S13..26`. A trailing `,NN` counts von Neumann neighbours and `,NM`
+/// (the default) Moore ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rule3d {
+    /// Bit `n` is set when a dead cell with `n` live neighbours is born.
+    birth: u32,
+    /// Bit `n` is set when a live cell with `n` live neighbours survives.
+    survival: u32,
+    neighborhood: Neighborhood,
+}
+
+impl Default for Rule3d {
+    fn default() -> Rule3d {
+        Rule3d::BAYS_4555
+    }
+}
+
+impl Rule3d {
+    /// Bays' Life 4555, which has a glider.
+    pub const BAYS_4555: Rule3d = Rule3d {
+        birth: 1 << 5,
+        survival: 1 << 4 | 1 << 5,
+        neighborhood: Neighborhood::Moore,
+    };
+
+    /// Bays' Life 5766, whose gliders are rarer still.
+    pub const BAYS_5766: Rule3d = Rule3d {
+        birth: 1 << 6,
+        survival: 1 << 5 | 1 << 6 | 1 << 7,
+        neighborhood: Neighborhood::Moore,
+    };
+
+    pub fn new(birth: &[u8], survival: &[u8], neighborhood: Neighborhood) -> Rule3d {
+        let bits = |counts: &[u8]| counts.iter().fold(0u32, |bits, &n| bits | 1 << n);
+        Rule3d {
+            birth: bits(birth),
+            survival: bits(survival),
+            neighborhood,
+        }
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    /// Number of neighbours of every cell.
+    pub fn neighbors(&self) -> u8 {
+        self.offsets().len() as u8
+    }
+
+    /// Offsets `[dx, dy, dz]` of the neighbours of a cell.
+    pub fn offsets(&self) -> &'static [[i32; 3]] {
+        match self.neighborhood {
+            Neighborhood::Moore => &MOORE,
+            Neighborhood::VonNeumann => &VON_NEUMANN,
+        }
+    }
+
+    pub fn is_born(&self, count: u8) -> bool {
+        self.birth >> count & 1 == 1
+    }
+
+    pub fn survives(&self, count: u8) -> bool {
+        self.survival >> count & 1 == 1
+    }
+
+    /// Whether a cell is alive next generation, given whether it is alive
+    /// now and its count of live neighbours.
+    pub fn next(&self, alive: bool, count: u8) -> bool {
+        if alive {
+            self.survives(count)
+        } else {
+            self.is_born(count)
+        }
+    }
+}
+
+/// The ends of the run of counts in `bits`, if they are all in one run.
+fn run(bits: u32) -> Option<(u32, u32)> {
+    let (low, high) = (bits.trailing_zeros(), 31 - bits.leading_zeros());
+    (bits != 0 && bits >> low == (1 << (high - low + 1)) - 1).then_some((low, high))
+}
+
+pub(crate) fn write_counts(f: &mut fmt::Formatter<'_>, bits: u32) -> fmt::Result {
+    let counts: Vec<u32> = (0..32).filter(|n| bits >> n & 1 == 1).collect();
+    if counts.iter().all(|&n| n <= 9) {
+        counts.iter().try_for_each(|n| write!(f, "{}", n))
+    } else {
+        let counts: Vec<String> = counts.iter().map(|n| n.to_string()).collect();
+        write!(f, "{}", counts.join(","))
+    }
+}
+
+impl fmt::Display for Rule3d {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (run(self.survival), run(self.birth)) {
+            (Some((el, eu)), Some((fl, fu))) if eu <= 9 && fu <= 9 => {
+                write!(f, "{}{}{}{}", el, eu, fl, fu)?
+            }
+            (Some((el, eu)), Some((fl, fu))) => write!(f, "{}/{}/{}/{}", el, eu, fl, fu)?,
+            _ => {
+                write!(f, "B")?;
+                write_counts(f, self.birth)?;
+                write!(f, "/S")?;
+                write_counts(f, self.survival)?;
+            }
+        }
+        if self.neighborhood == Neighborhood::VonNeumann {
+            write!(f, ",NN")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a set of counts: single digits run together, or numbers and
+/// `a..b` ranges between commas. Counts above `neighbors`, which is at most
+/// 31, are an error saying `too_many`.
+pub(crate) fn parse_counts(
+    rule: &str,
+    counts: &str,
+    neighbors: u8,
+    too_many: &'static str,
+) -> Result<u32, ParseRuleError> {
+    let invalid = || ParseRuleError::new(rule, "expected neighbour counts");
+    let mut bits = 0u32;
+    let mut add = |low: u8, high: u8| {
+        if low > high || high > neighbors {
+            return Err(ParseRuleError::new(rule, too_many));
+        }
+        bits |= (low..=high).fold(0, |bits, n| bits | 1 << n);
+        Ok(())
+    };
+    if counts.contains([',', '.']) {
+        for item in counts.split(',') {
+            let (low, high) = item.split_once("..").unwrap_or((item, item));
+            let low = low.trim().parse().map_err(|_| invalid())?;
+            let high = high.trim().parse().map_err(|_| invalid())?;
+            add(low, high)?;
+        }
+    } else {
+        for c in counts.trim().chars() {
+            let n = c.to_digit(10).ok_or_else(invalid)? as u8;
+            add(n, n)?;
+        }
+    }
+    Ok(bits)
+}
+
+const TOO_MANY: &str = "neighbour counts go up to 26, or 6 with ,NN";
+
+impl FromStr for Rule3d {
+    type Err = ParseRuleError;
+
+    fn from_str(rule: &str) -> Result<Rule3d, ParseRuleError> {
+        let s = rule.trim();
+        let (s, neighborhood) = match s.rsplit_once(',') {
+            Some((s, n)) if n.trim().eq_ignore_ascii_case("NM") => (s, Neighborhood::Moore),
+            Some((s, n)) if n.trim().eq_ignore_ascii_case("NN") => (s, Neighborhood::VonNeumann),
+            _ => (s, Neighborhood::Moore),
+        };
+        let neighbors = Rule3d::new(&[], &[], neighborhood).neighbors();
+        let mut out = Rule3d::new(&[], &[], neighborhood);
+        if s.starts_with(['B', 'b', 'S', 's']) {
+            for part in s.split('/') {
+                let (kind, counts) = part.split_at(part.chars().next().map_or(0, char::len_utf8));
+                match kind {
+                    "B" | "b" => out.birth = parse_counts(rule, counts, neighbors, TOO_MANY)?,
+                    "S" | "s" => out.survival = parse_counts(rule, counts, neighbors, TOO_MANY)?,
+                    _ => return Err(ParseRuleError::new(rule, "expected B and S parts")),
+                }
+            }
+            return Ok(out);
+        }
+        let bounds: Vec<&str> = if s.contains('/') {
+            s.split('/').collect()
+        } else {
+            (0..s.len()).filter_map(|i| s.get(i..i + 1)).collect()
+        };
+        let bounds: Vec<u8> = bounds
+            .iter()
+            .map(|n| n.trim().parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| ParseRuleError::new(rule, "expected Bays' EₗEᵤFₗFᵤ or B/S notation"))?;
+        let &[el, eu, fl, fu] = bounds.as_slice() else {
+            return Err(ParseRuleError::new(rule, "expected four bounds"));
+        };
+        out.survival = parse_counts(rule, &format!("{}..{}", el, eu), neighbors, TOO_MANY)?;
+        out.birth = parse_counts(rule, &format!("{}..{}", fl, fu), neighbors, TOO_MANY)?;
+        Ok(out)
+    }
+}
+
+/// A finite box of `x` by `y` by `z` cells stepped under a `Rule3d`. Cells
+/// beyond the box stay dead.
+pub struct Universe3d {
+    size: [usize; 3],
+    rule: Rule3d,
+    cells: Vec<bool>,
+    next: Vec<bool>,
+    now: u64,
+}
+
+impl Universe3d {
+    pub fn new(x: usize, y: usize, z: usize, rule: Rule3d) -> Universe3d {
+        Universe3d {
+            size: [x, y, z],
+            rule,
+            cells: vec![false; x * y * z],
+            next: vec![false; x * y * z],
+            now: 0,
+        }
+    }
+
+    /// The size of the box as `[x, y, z]`.
+    pub fn size(&self) -> [usize; 3] {
+        self.size
+    }
+
+    pub fn rule(&self) -> Rule3d {
+        self.rule
+    }
+
+    fn index(&self, x: i64, y: i64, z: i64) -> Option<usize> {
+        let [sx, sy, sz] = self.size.map(|n| n as i64);
+        ((0..sx).contains(&x) && (0..sy).contains(&y) && (0..sz).contains(&z))
+            .then(|| ((z * sy + y) * sx + x) as usize)
+    }
+
+    pub fn get(&self, x: i64, y: i64, z: i64) -> bool {
+        self.index(x, y, z).is_some_and(|i| self.cells[i])
+    }
+
+    pub fn set(&mut self, x: i64, y: i64, z: i64, alive: bool) {
+        if let Some(i) = self.index(x, y, z) {
+            self.cells[i] = alive;
+        }
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.now
+    }
+
+    pub fn population(&self) -> u64 {
+        self.cells.iter().filter(|&&alive| alive).count() as u64
+    }
+
+    pub fn tick(&mut self) {
+        let [sx, sy, sz] = self.size.map(|n| n as i64);
+        let mut next = std::mem::take(&mut self.next);
+        for z in 0..sz {
+            for y in 0..sy {
+                for x in 0..sx {
+                    let count = self
+                        .rule
+                        .offsets()
+                        .iter()
+                        .filter(|[dx, dy, dz]| {
+                            self.get(x + *dx as i64, y + *dy as i64, z + *dz as i64)
+                        })
+                        .count() as u8;
+                    next[((z * sy + y) * sx + x) as usize] =
+                        self.rule.next(self.get(x, y, z), count);
+                }
+            }
+        }
+        self.next = std::mem::replace(&mut self.cells, next);
+        self.now += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bays_and_bs_notations() {
+        let bays: Rule3d = "4555".parse().unwrap();
+        assert_eq!(bays, Rule3d::BAYS_4555);
+        assert_eq!(bays, "B5/S45".parse().unwrap());
+        assert_eq!(bays, "4/5/5/5,NM".parse().unwrap());
+        assert_eq!(Rule3d::BAYS_5766, "5766".parse().unwrap());
+        assert_eq!(Rule3d::BAYS_5766.to_string(), "5766");
+
+        let wide: Rule3d = "B14..19/S13..26".parse().unwrap();
+        assert!(wide.is_born(14) && wide.is_born(19) && !wide.is_born(20));
+        assert!(wide.survives(26) && !wide.survives(12));
+        assert_eq!(wide.to_string(), "13/26/14/19");
+        let gaps: Rule3d = "B4,12/S1..3".parse().unwrap();
+        assert_eq!(gaps.to_string(), "B4,12/S123");
+
+        let von_neumann: Rule3d = "B1/S0,NN".parse().unwrap();
+        assert_eq!(von_neumann.neighbors(), 6);
+        assert_eq!(von_neumann.to_string(), "0011,NN");
+
+        for bad in ["", "455", "45555", "B5,27/S4", "B7/S,NN", "4x55", "2/1/5/5"] {
+            assert!(bad.parse::<Rule3d>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn neighbourhoods_are_the_cube_and_its_faces() {
+        assert_eq!(Rule3d::BAYS_4555.neighbors(), 26);
+        let mut seen = MOORE.to_vec();
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen.len(), 26);
+        assert!(!seen.contains(&[0, 0, 0]));
+        assert!(VON_NEUMANN
+            .iter()
+            .all(|o| o.iter().map(|d| d.abs()).sum::<i32>() == 1));
+    }
+
+    #[test]
+    fn cube_is_still_under_5766() {
+        let mut universe = Universe3d::new(6, 6, 6, Rule3d::BAYS_5766);
+        for i in 0..8 {
+            universe.set(2 + (i & 1), 2 + (i >> 1 & 1), 2 + (i >> 2), true);
+        }
+        universe.tick();
+        assert_eq!(universe.population(), 8);
+        assert!(universe.get(3, 3, 3));
+        // With only 7 neighbours each the same cube dies out under 4555.
+        let mut universe = Universe3d::new(6, 6, 6, Rule3d::BAYS_4555);
+        for i in 0..8 {
+            universe.set(2 + (i & 1), 2 + (i >> 1 & 1), 2 + (i >> 2), true);
+        }
+        universe.tick();
+        assert_eq!(universe.population(), 0);
+    }
+}
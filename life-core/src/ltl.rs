@@ -0,0 +1,395 @@
+use std::fmt;
+use std::mem::size_of;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use crate::engine::Engine;
+use crate::parallel::for_each_band;
+use crate::rule::ParseRuleError;
+use crate::topology::Topology;
+
+/// The cells counted as neighbours within the radius of a Larger-than-Life rule.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Neighborhood {
+    /// The square `|dx| <= r && |dy| <= r`.
+    Moore,
+    /// The diamond `|dx| + |dy| <= r`.
+    VonNeumann,
+}
+
+/// A Larger-than-Life rule, in Golly's notation
+/// `Rr,Cc,Mm,Smin..max,Bmin..max,Nn`: neighbours are counted out to radius
+/// `r`, including the cell itself when `m` is 1, and a cell is born or
+/// survives when the count falls in the birth or survival range. More than
+/// two states `c` make dying cells fade as in Generations rules.
+///
+/// Bosco's Rule is `R5,C0,M1,S34..58,B34..45,NM`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LtlRule {
+    pub radius: u16,
+    /// 2 for two-state rules; Golly writes `C0` for those.
+    pub states: u8,
+    /// Whether a cell counts itself among its neighbours.
+    pub middle: bool,
+    pub survival: RangeInclusive<u32>,
+    pub birth: RangeInclusive<u32>,
+    pub neighborhood: Neighborhood,
+}
+
+impl LtlRule {
+    /// The state of a cell next generation, given its state now and its
+    /// count of live neighbours.
+    pub fn next(&self, state: u8, count: u32) -> u8 {
+        match state {
+            0 => self.birth.contains(&count) as u8,
+            1 if self.survival.contains(&count) => 1,
+            _ if state + 1 < self.states => state + 1,
+            _ => 0,
+        }
+    }
+}
+
+impl fmt::Display for LtlRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "R{},C{},M{},S{}..{},B{}..{},N{}",
+            self.radius,
+            if self.states > 2 { self.states } else { 0 },
+            self.middle as u8,
+            self.survival.start(),
+            self.survival.end(),
+            self.birth.start(),
+            self.birth.end(),
+            match self.neighborhood {
+                Neighborhood::Moore => 'M',
+                Neighborhood::VonNeumann => 'N',
+            }
+        )
+    }
+}
+
+impl FromStr for LtlRule {
+    type Err = ParseRuleError;
+
+    fn from_str(s: &str) -> Result<LtlRule, ParseRuleError> {
+        let number = |digits: &str| -> Result<u32, ParseRuleError> {
+            digits
+                .parse()
+                .map_err(|_| ParseRuleError::new(s, "expected a number"))
+        };
+        let range = |digits: &str| -> Result<RangeInclusive<u32>, ParseRuleError> {
+            match digits.split_once("..") {
+                Some((min, max)) => Ok(number(min)?..=number(max)?),
+                None => Err(ParseRuleError::new(s, "expected a range like 34..58")),
+            }
+        };
+
+        let (mut radius, mut states, mut middle) = (None, 0, false);
+        let (mut survival, mut birth, mut neighborhood) = (None, None, Neighborhood::Moore);
+        for part in s.trim().split(',') {
+            let mut chars = part.chars();
+            let key = chars.next().map(|c| c.to_ascii_uppercase());
+            let value = chars.as_str();
+            match key {
+                Some('R') => radius = Some(number(value)?),
+                Some('C') => states = number(value)?,
+                Some('M') => middle = number(value)? == 1,
+                Some('S') => survival = Some(range(value)?),
+                Some('B') => birth = Some(range(value)?),
+                Some('N') => {
+                    neighborhood = match value {
+                        "M" | "m" => Neighborhood::Moore,
+                        "N" | "n" => Neighborhood::VonNeumann,
+                        _ => {
+                            return Err(ParseRuleError::new(
+                                s,
+                                "the neighbourhood must be NM or NN",
+                            ))
+                        }
+                    }
+                }
+                _ => {
+                    return Err(ParseRuleError::new(
+                        s,
+                        "unknown part, expected R, C, M, S, B or N",
+                    ))
+                }
+            }
+        }
+        let radius = match radius {
+            Some(r @ 1..=500) => r as u16,
+            // Golly's limit.
+            Some(_) => return Err(ParseRuleError::new(s, "the radius must be 1 to 500")),
+            None => return Err(ParseRuleError::new(s, "missing the radius")),
+        };
+        if states > 255 {
+            return Err(ParseRuleError::new(s, "at most 255 states"));
+        }
+        match (survival, birth) {
+            (Some(survival), Some(birth)) => Ok(LtlRule {
+                radius,
+                states: (states as u8).max(2),
+                middle,
+                survival,
+                birth,
+                neighborhood,
+            }),
+            _ => Err(ParseRuleError::new(
+                s,
+                "missing the survival or birth range",
+            )),
+        }
+    }
+}
+
+/// A finite board run under a Larger-than-Life rule.
+///
+/// Every tick builds a summed-area table of the live cells, padded by the
+/// radius on every side according to the topology, so a Moore neighbourhood
+/// is counted in four lookups whatever its radius, and a von Neumann one in a
+/// lookup pair per row.
+pub struct LargerThanLife {
+    width: usize,
+    height: usize,
+    topology: Topology,
+    rule: LtlRule,
+    /// Golly state numbers: 0 dead, 1 alive and 2 and up dying.
+    cells: Vec<u8>,
+    next: Vec<u8>,
+    /// Scratch space for the summed-area table, kept to reuse its allocation.
+    sums: Vec<u32>,
+    now: u64,
+    threads: usize,
+}
+
+impl LargerThanLife {
+    pub fn new(width: usize, height: usize, rule: LtlRule) -> LargerThanLife {
+        LargerThanLife::with_topology(width, height, rule, Topology::Plane)
+    }
+
+    pub fn with_topology(
+        width: usize,
+        height: usize,
+        rule: LtlRule,
+        topology: Topology,
+    ) -> LargerThanLife {
+        LargerThanLife {
+            width,
+            height,
+            topology,
+            rule,
+            cells: vec![0; width * height],
+            next: vec![0; width * height],
+            sums: Vec::new(),
+            now: 0,
+            threads: 1,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn rule(&self) -> &LtlRule {
+        &self.rule
+    }
+
+    /// Splits each tick into bands of rows stepped on `threads` threads. The
+    /// result is the same for any number of threads.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    /// Fills `sums` with the summed-area table of the live cells on the board
+    /// grown by the radius on each side: entry `(x, y)` of the `(w + 2r + 1)`
+    /// wide table holds the live cells above and to the left of padded cell
+    /// `(x, y)`.
+    fn sum_live_cells(&mut self) {
+        let r = self.rule.radius as i64;
+        let stride = self.width + 2 * r as usize + 1;
+        let rows = self.height + 2 * r as usize + 1;
+        self.sums.clear();
+        self.sums.resize(stride * rows, 0);
+        for y in 1..rows {
+            let mut row_sum = 0;
+            for x in 1..stride {
+                let alive = self
+                    .topology
+                    .locate(x as i64 - 1 - r, y as i64 - 1 - r, self.width, self.height)
+                    .is_some_and(|(w, h)| self.cells[h * self.width + w] == 1);
+                row_sum += alive as u32;
+                self.sums[y * stride + x] = self.sums[(y - 1) * stride + x] + row_sum;
+            }
+        }
+    }
+
+    /// Live neighbours of board cell `(w, h)`, read off the summed-area table.
+    fn count(&self, w: usize, h: usize) -> u32 {
+        let r = self.rule.radius as usize;
+        let stride = self.width + 2 * r + 1;
+        // Live cells in padded columns `x0..x1` and rows `y0..y1`.
+        let area = |x0: usize, y0: usize, x1: usize, y1: usize| {
+            self.sums[y1 * stride + x1] + self.sums[y0 * stride + x0]
+                - self.sums[y0 * stride + x1]
+                - self.sums[y1 * stride + x0]
+        };
+        // Board cell `(w, h)` is padded cell `(w + r, h + r)`.
+        let count = match self.rule.neighborhood {
+            Neighborhood::Moore => area(w, h, w + 2 * r + 1, h + 2 * r + 1),
+            Neighborhood::VonNeumann => (0..=2 * r)
+                .map(|dy| {
+                    let reach = r - r.abs_diff(dy);
+                    area(w + r - reach, h + dy, w + r + reach + 1, h + dy + 1)
+                })
+                .sum(),
+        };
+        if self.rule.middle || self.cells[h * self.width + w] != 1 {
+            count
+        } else {
+            count - 1
+        }
+    }
+}
+
+impl Engine for LargerThanLife {
+    fn tick(&mut self) {
+        self.now += 1;
+        self.sum_live_cells();
+        let mut next = std::mem::take(&mut self.next);
+        let this = &*self;
+        for_each_band(&mut next, self.width, self.threads, |first, rows| {
+            for (h, row) in (first..).zip(rows.chunks_mut(this.width)) {
+                for (w, cell) in row.iter_mut().enumerate() {
+                    *cell = this
+                        .rule
+                        .next(this.cells[h * this.width + w], this.count(w, h));
+                }
+            }
+        });
+        self.next = std::mem::replace(&mut self.cells, next);
+    }
+
+    fn generation(&self) -> u64 {
+        self.now
+    }
+
+    fn set_generation(&mut self, generation: u64) {
+        self.now = generation;
+    }
+
+    fn population(&self) -> u64 {
+        self.cells.iter().filter(|&&state| state == 1).count() as u64
+    }
+
+    fn get(&self, x: i64, y: i64) -> bool {
+        self.state(x, y) == 1
+    }
+
+    fn state(&self, x: i64, y: i64) -> u8 {
+        self.topology
+            .locate(x, y, self.width, self.height)
+            .map_or(0, |(w, h)| self.cells[h * self.width + w])
+    }
+
+    fn set(&mut self, x: i64, y: i64, alive: bool) {
+        if let Some((w, h)) = self.topology.locate(x, y, self.width, self.height) {
+            self.cells[h * self.width + w] = alive as u8;
+        }
+    }
+
+    fn set_state(&mut self, x: i64, y: i64, state: u8) {
+        assert!(
+            state < self.rule.states,
+            "{} has no state {}",
+            self.rule,
+            state
+        );
+        if let Some((w, h)) = self.topology.locate(x, y, self.width, self.height) {
+            self.cells[h * self.width + w] = state;
+        }
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64, u8)> {
+        let mut out = Vec::new();
+        for h in 0..self.height {
+            for w in 0..self.width {
+                let state = self.cells[h * self.width + w];
+                if state != 0 {
+                    out.push((w as i64, h as i64, state));
+                }
+            }
+        }
+        out
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.cells.len() + self.next.len() + self.sums.capacity() * size_of::<u32>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::soup;
+    use crate::universe::Universe;
+
+    #[test]
+    fn parses_golly_notation() {
+        let bosco: LtlRule = "R5,C0,M1,S34..58,B34..45,NM".parse().unwrap();
+        assert_eq!(bosco.radius, 5);
+        assert_eq!(bosco.states, 2);
+        assert!(bosco.middle);
+        assert_eq!(bosco.survival, 34..=58);
+        assert_eq!(bosco.birth, 34..=45);
+        assert_eq!(bosco.to_string(), "R5,C0,M1,S34..58,B34..45,NM");
+        let diamond: LtlRule = "R3,C4,M0,S2..5,B3..3,NN".parse().unwrap();
+        assert_eq!(diamond.neighborhood, Neighborhood::VonNeumann);
+        assert_eq!(diamond.states, 4);
+        assert!("R0,C0,M0,S2..3,B3..3,NM".parse::<LtlRule>().is_err());
+        assert!("R1,C0,M0,S2..3,NM".parse::<LtlRule>().is_err());
+        assert!("R1,C0,M0,S2..3,B3,NM".parse::<LtlRule>().is_err());
+    }
+
+    #[test]
+    fn radius_one_is_conway() {
+        let rule = "R1,C0,M0,S2..3,B3..3,NM".parse().unwrap();
+        let (width, height) = (40, 30);
+        let mut dense = Universe::with_topology(width, height, Topology::Torus);
+        let mut ltl = LargerThanLife::with_topology(width, height, rule, Topology::Torus);
+        ltl.set_threads(3);
+        soup(
+            5,
+            0..width as i64,
+            0..height as i64,
+            &mut [&mut dense, &mut ltl],
+        );
+        for _ in 0..20 {
+            dense.tick();
+            ltl.tick();
+            for y in 0..height as i64 {
+                for x in 0..width as i64 {
+                    assert_eq!(dense.get(x, y), ltl.get(x, y), "({}, {})", x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn von_neumann_neighbourhood_is_a_diamond() {
+        let rule = "R2,C0,M0,S0..0,B1..1,NN".parse().unwrap();
+        let mut ltl = LargerThanLife::new(9, 9, rule);
+        ltl.set(4, 4, true);
+        ltl.tick();
+        for y in 0..9i64 {
+            for x in 0..9i64 {
+                let d = (x - 4).abs() + (y - 4).abs();
+                assert_eq!(ltl.get(x, y), d <= 2, "({}, {})", x, y);
+            }
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::engine::Engine;
+use crate::parallel::for_each_band;
+use crate::rule::ParseRuleError;
+use crate::topology::Topology;
+
+/// A block rule on the Margolus neighbourhood: the board is cut into 2x2
+/// blocks, and each block is replaced as a whole by the entry of a 16-entry
+/// table. Blocks are numbered by their live cells, 1 for the top left, 2 top
+/// right, 4 bottom left and 8 bottom right.
+///
+/// Rules are written in MCell's notation, `MS,D` followed by the 16 entries
+/// between semicolons, or by name: `Critters`, `Tron`, `BBM` (the billiard-ball
+/// machine) or `Sand`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BlockRule {
+    table: [u8; 16],
+}
+
+const NAMED: [(&str, BlockRule); 4] = [
+    ("Critters", BlockRule::CRITTERS),
+    ("Tron", BlockRule::TRON),
+    ("BBM", BlockRule::BILLIARD_BALL),
+    ("Sand", BlockRule::SAND),
+];
+
+impl BlockRule {
+    /// Blocks of two stay as they are; the others are inverted, and those
+    /// that had three cells are also turned half way round.
+    pub const CRITTERS: BlockRule =
+        BlockRule::new([15, 14, 13, 3, 11, 5, 6, 1, 7, 9, 10, 2, 12, 4, 8, 0]);
+
+    /// Blocks all dead or all alive are inverted.
+    pub const TRON: BlockRule =
+        BlockRule::new([15, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 0]);
+
+    /// A lone ball crosses its block diagonally; two balls meeting head on
+    /// bounce off at right angles.
+    pub const BILLIARD_BALL: BlockRule =
+        BlockRule::new([0, 8, 4, 3, 2, 5, 9, 7, 1, 6, 10, 11, 12, 13, 14, 15]);
+
+    /// Grains fall down, or slide down diagonally when the cell below is
+    /// taken.
+    pub const SAND: BlockRule =
+        BlockRule::new([0, 4, 8, 12, 4, 12, 12, 13, 8, 12, 12, 14, 12, 13, 14, 15]);
+
+    /// Panics unless every entry is a block, from 0 to 15.
+    pub const fn new(table: [u8; 16]) -> BlockRule {
+        let mut i = 0;
+        while i < 16 {
+            assert!(table[i] < 16, "blocks are numbered 0 to 15");
+            i += 1;
+        }
+        BlockRule { table }
+    }
+
+    /// The block that `block` turns into.
+    pub fn next(&self, block: u8) -> u8 {
+        self.table[block as usize]
+    }
+
+    /// Whether every block keeps its number of live cells.
+    pub fn conserves_population(&self) -> bool {
+        (0..16u8).all(|block| block.count_ones() == self.next(block).count_ones())
+    }
+}
+
+impl fmt::Display for BlockRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let table: Vec<String> = self.table.iter().map(|n| n.to_string()).collect();
+        write!(f, "MS,D{}", table.join(";"))
+    }
+}
+
+impl FromStr for BlockRule {
+    type Err = ParseRuleError;
+
+    fn from_str(rule: &str) -> Result<BlockRule, ParseRuleError> {
+        let s = rule.trim();
+        if let Some(&(_, named)) = NAMED.iter().find(|(name, _)| name.eq_ignore_ascii_case(s)) {
+            return Ok(named);
+        }
+        let table = s
+            .get(..4)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("MS,D"))
+            .map(|_| &s[4..])
+            .ok_or_else(|| ParseRuleError::new(rule, "expected MS,D and a block table"))?;
+        let table: Vec<u8> = table
+            .split(';')
+            .map(|n| n.trim().parse().ok().filter(|&n| n < 16))
+            .collect::<Option<_>>()
+            .ok_or_else(|| ParseRuleError::new(rule, "blocks are numbered 0 to 15"))?;
+        let table: [u8; 16] = table
+            .try_into()
+            .map_err(|_| ParseRuleError::new(rule, "expected 16 blocks"))?;
+        Ok(BlockRule { table })
+    }
+}
+
+/// A finite board stepped under a `BlockRule`.
+///
+/// Even generations cut the board into blocks whose top left corner has even
+/// coordinates, odd ones into blocks shifted one cell down and to the right,
+/// so information crosses block edges. Blocks hanging off a plane's edge stay
+/// as they are, which makes the edge a wall; a board whose edges are joined
+/// needs even sizes for the blocks to fit.
+pub struct Margolus {
+    width: usize,
+    height: usize,
+    topology: Topology,
+    rule: BlockRule,
+    cells: Vec<u8>,
+    next: Vec<u8>,
+    now: u64,
+    threads: usize,
+}
+
+impl Margolus {
+    pub fn new(width: usize, height: usize, rule: BlockRule) -> Margolus {
+        Margolus::with_topology(width, height, rule, Topology::Plane)
+    }
+
+    pub fn with_topology(
+        width: usize,
+        height: usize,
+        rule: BlockRule,
+        topology: Topology,
+    ) -> Margolus {
+        Margolus {
+            width,
+            height,
+            topology,
+            rule,
+            cells: vec![0; width * height],
+            next: vec![0; width * height],
+            now: 0,
+            threads: 1,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn rule(&self) -> BlockRule {
+        self.rule
+    }
+
+    /// Splits each tick into bands of rows stepped on `threads` threads. The
+    /// result is the same for any number of threads.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    /// The top left corner of the block holding `(x, y)` this generation.
+    fn block_of(&self, x: i64, y: i64) -> (i64, i64) {
+        let phase = (self.now % 2) as i64;
+        (x - (x - phase).rem_euclid(2), y - (y - phase).rem_euclid(2))
+    }
+
+    /// The cell `(w, h)` after its block has been replaced. Blocks hanging
+    /// off the edge of the board stay as they are.
+    fn next_cell(&self, w: usize, h: usize) -> u8 {
+        let (x, y) = (w as i64, h as i64);
+        let (bx, by) = self.block_of(x, y);
+        let mut block = 0;
+        for i in 0..4 {
+            match self
+                .topology
+                .locate(bx + i % 2, by + i / 2, self.width, self.height)
+            {
+                Some((w, h)) => block |= self.cells[h * self.width + w] << i,
+                None => return self.cells[h * self.width + w],
+            }
+        }
+        let i = (x - bx) + 2 * (y - by);
+        self.rule.next(block) >> i & 1
+    }
+}
+
+impl Engine for Margolus {
+    fn tick(&mut self) {
+        let mut next = std::mem::take(&mut self.next);
+        let this = &*self;
+        for_each_band(&mut next, self.width, self.threads, |first, rows| {
+            for (h, row) in (first..).zip(rows.chunks_mut(this.width)) {
+                for (w, cell) in row.iter_mut().enumerate() {
+                    *cell = this.next_cell(w, h);
+                }
+            }
+        });
+        self.next = std::mem::replace(&mut self.cells, next);
+        self.now += 1;
+    }
+
+    fn generation(&self) -> u64 {
+        self.now
+    }
+
+    fn set_generation(&mut self, generation: u64) {
+        self.now = generation;
+    }
+
+    fn population(&self) -> u64 {
+        self.cells.iter().filter(|&&alive| alive == 1).count() as u64
+    }
+
+    fn get(&self, x: i64, y: i64) -> bool {
+        self.topology
+            .locate(x, y, self.width, self.height)
+            .is_some_and(|(w, h)| self.cells[h * self.width + w] == 1)
+    }
+
+    fn set(&mut self, x: i64, y: i64, alive: bool) {
+        if let Some((w, h)) = self.topology.locate(x, y, self.width, self.height) {
+            self.cells[h * self.width + w] = alive as u8;
+        }
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64, u8)> {
+        let mut out = Vec::new();
+        for h in 0..self.height {
+            for w in 0..self.width {
+                if self.cells[h * self.width + w] == 1 {
+                    out.push((w as i64, h as i64, 1));
+                }
+            }
+        }
+        out
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.cells.len() + self.next.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_mcell_notation_and_names() {
+        let critters: BlockRule = "MS,D15;14;13;3;11;5;6;1;7;9;10;2;12;4;8;0".parse().unwrap();
+        assert_eq!(critters, BlockRule::CRITTERS);
+        assert_eq!("critters".parse::<BlockRule>().unwrap(), critters);
+        assert_eq!(
+            BlockRule::BILLIARD_BALL
+                .to_string()
+                .parse::<BlockRule>()
+                .unwrap(),
+            BlockRule::BILLIARD_BALL
+        );
+        assert!(BlockRule::BILLIARD_BALL.conserves_population());
+        assert!(BlockRule::SAND.conserves_population());
+        assert!(!BlockRule::CRITTERS.conserves_population());
+        for bad in [
+            "",
+            "MS,D1;2",
+            "MS,D0;1;2;3;4;5;6;7;8;9;10;11;12;13;14;16",
+            "B3/S23",
+        ] {
+            assert!(bad.parse::<BlockRule>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn billiard_ball_travels_diagonally() {
+        let mut board = Margolus::with_topology(16, 16, BlockRule::BILLIARD_BALL, Topology::Torus);
+        // A ball in the bottom right of its block heads up and left, one cell
+        // a generation, and across the edge.
+        board.set(5, 5, true);
+        for t in 1..=5 {
+            board.tick();
+            assert_eq!(board.live_cells(), vec![(5 - t, 5 - t, 1)]);
+        }
+        board.tick();
+        assert_eq!(board.live_cells(), vec![(15, 15, 1)]);
+        // One in the top left heads the other way.
+        let mut board = Margolus::with_topology(16, 16, BlockRule::BILLIARD_BALL, Topology::Torus);
+        board.set(4, 4, true);
+        board.step(4);
+        assert_eq!(board.live_cells(), vec![(8, 8, 1)]);
+    }
+
+    #[test]
+    fn sand_piles_up_at_the_bottom() {
+        let mut board = Margolus::new(8, 8, BlockRule::SAND);
+        for x in 2..6 {
+            board.set(x, 0, true);
+            board.set(x, 1, true);
+        }
+        board.step(30);
+        // The edge holds the sand in, and it has settled.
+        let settled = board.live_cells();
+        assert_eq!(settled.len(), 8);
+        assert!(settled.iter().all(|&(_, y, _)| y >= 6), "{:?}", settled);
+        board.step(2);
+        assert_eq!(board.live_cells(), settled);
+    }
+}
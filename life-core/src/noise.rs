@@ -0,0 +1,296 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::engine::Engine;
+use crate::random::Rng;
+use crate::rule::ParseRuleError;
+
+/// A rectangle of cells, `width` x `height` from `(x, y)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub x: i64,
+    pub y: i64,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Region {
+    pub fn contains(&self, x: i64, y: i64) -> bool {
+        (self.x..self.x + self.width as i64).contains(&x)
+            && (self.y..self.y + self.height as i64).contains(&y)
+    }
+}
+
+/// Random births and deaths laid over any engine after it steps.
+///
+/// Each dead cell comes alive with a probability, and each live cell dies
+/// with one, that may depend on its number of live Moore neighbours, which
+/// turns a rule into a probabilistic variant of itself: `B2=0.05` lets dead
+/// cells with two neighbours be born one time in twenty on top of what the
+/// rule does. The noise is confined to its regions, or to the board given to
+/// `apply` when it has none, and draws every number from the `Rng` it is
+/// handed, so a seeded run is replayed exactly.
+///
+/// Noise is written as terms between slashes: `B0.001` for a birth
+/// probability whatever the neighbours, `B2=0.05` for cells with two live
+/// neighbours, `D` likewise for deaths, and `@10,20,30x40` for a region of
+/// 30 x 40 cells from (10, 20). Later terms override earlier ones, so
+/// `B0.001/B3=0` leaves cells with three neighbours to the rule alone.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Noise {
+    births: [f64; 9],
+    deaths: [f64; 9],
+    regions: Vec<Region>,
+}
+
+impl Noise {
+    /// Sets the probability of a dead cell coming alive, whatever its
+    /// neighbours.
+    pub fn set_birth(&mut self, probability: f64) {
+        self.births = [probability; 9];
+    }
+
+    /// Sets the probability of a dead cell with `neighbours` live neighbours
+    /// coming alive.
+    pub fn set_birth_with(&mut self, neighbours: usize, probability: f64) {
+        self.births[neighbours] = probability;
+    }
+
+    /// Sets the probability of a live cell dying, whatever its neighbours.
+    pub fn set_death(&mut self, probability: f64) {
+        self.deaths = [probability; 9];
+    }
+
+    /// Sets the probability of a live cell with `neighbours` live neighbours
+    /// dying.
+    pub fn set_death_with(&mut self, neighbours: usize, probability: f64) {
+        self.deaths[neighbours] = probability;
+    }
+
+    /// Confines the noise to `region` as well as any regions added before.
+    pub fn add_region(&mut self, region: Region) {
+        self.regions.push(region);
+    }
+
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    /// Whether no cell can ever change.
+    pub fn is_quiet(&self) -> bool {
+        self.births.iter().chain(&self.deaths).all(|&p| p <= 0.0)
+    }
+
+    /// Flips cells of `engine` at random, within the regions or else the
+    /// `width` x `height` board from the origin, and returns how many.
+    ///
+    /// Neighbours are counted on the generation as it was before any cell
+    /// flipped, so the order cells are visited in makes no difference.
+    pub fn apply(
+        &self,
+        engine: &mut dyn Engine,
+        width: usize,
+        height: usize,
+        rng: &mut Rng,
+    ) -> u64 {
+        if self.is_quiet() {
+            return 0;
+        }
+        let board = [Region {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        }];
+        let regions = if self.regions.is_empty() {
+            &board[..]
+        } else {
+            &self.regions[..]
+        };
+        let counted = self.births.iter().any(|&p| p != self.births[0])
+            || self.deaths.iter().any(|&p| p != self.deaths[0]);
+        let mut flips = Vec::new();
+        for (i, region) in regions.iter().enumerate() {
+            for y in region.y..region.y + region.height as i64 {
+                for x in region.x..region.x + region.width as i64 {
+                    if regions[..i].iter().any(|earlier| earlier.contains(x, y)) {
+                        continue;
+                    }
+                    let neighbours = if counted { neighbours(engine, x, y) } else { 0 };
+                    let alive = engine.get(x, y);
+                    let p = if alive {
+                        self.deaths[neighbours]
+                    } else {
+                        self.births[neighbours]
+                    };
+                    if p > 0.0 && rng.gen_bool(p) {
+                        flips.push((x, y, !alive));
+                    }
+                }
+            }
+        }
+        for &(x, y, alive) in &flips {
+            engine.set(x, y, alive);
+        }
+        flips.len() as u64
+    }
+}
+
+fn neighbours(engine: &dyn Engine, x: i64, y: i64) -> usize {
+    let mut n = 0;
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            if (dx, dy) != (0, 0) && engine.get(x + dx, y + dy) {
+                n += 1;
+            }
+        }
+    }
+    n
+}
+
+impl fmt::Display for Noise {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut terms = Vec::new();
+        for (letter, probabilities) in [('B', &self.births), ('D', &self.deaths)] {
+            if probabilities.iter().all(|&p| p == probabilities[0]) {
+                if probabilities[0] > 0.0 {
+                    terms.push(format!("{}{}", letter, probabilities[0]));
+                }
+            } else {
+                for (n, &p) in probabilities.iter().enumerate().filter(|(_, &p)| p > 0.0) {
+                    terms.push(format!("{}{}={}", letter, n, p));
+                }
+            }
+        }
+        for region in &self.regions {
+            terms.push(format!(
+                "@{},{},{}x{}",
+                region.x, region.y, region.width, region.height
+            ));
+        }
+        write!(f, "{}", terms.join("/"))
+    }
+}
+
+impl FromStr for Noise {
+    type Err = ParseRuleError;
+
+    fn from_str(s: &str) -> Result<Noise, ParseRuleError> {
+        let mut noise = Noise::default();
+        for term in s.split('/').map(str::trim).filter(|term| !term.is_empty()) {
+            let (letter, rest) = term.split_at(term.chars().next().map_or(0, char::len_utf8));
+            match letter {
+                "@" => noise.add_region(parse_region(rest).ok_or_else(|| {
+                    ParseRuleError::new(s, "expected a region such as @10,20,30x40")
+                })?),
+                "B" | "b" | "D" | "d" => {
+                    let (neighbours, p) = match rest.split_once('=') {
+                        Some((n, p)) => (Some(n), p),
+                        None => (None, rest),
+                    };
+                    let p: f64 = p
+                        .parse()
+                        .ok()
+                        .filter(|p| (0.0..=1.0).contains(p))
+                        .ok_or_else(|| ParseRuleError::new(s, "probabilities run from 0 to 1"))?;
+                    let neighbours: Option<usize> = neighbours
+                        .map(|n| n.parse().ok().filter(|&n| n <= 8))
+                        .map(|n| {
+                            n.ok_or_else(|| ParseRuleError::new(s, "a cell has 0 to 8 neighbours"))
+                        })
+                        .transpose()?;
+                    match (letter.eq_ignore_ascii_case("B"), neighbours) {
+                        (true, None) => noise.set_birth(p),
+                        (true, Some(n)) => noise.set_birth_with(n, p),
+                        (false, None) => noise.set_death(p),
+                        (false, Some(n)) => noise.set_death_with(n, p),
+                    }
+                }
+                _ => return Err(ParseRuleError::new(s, "expected B, D or @ terms")),
+            }
+        }
+        Ok(noise)
+    }
+}
+
+/// `x,y,WxH`.
+fn parse_region(s: &str) -> Option<Region> {
+    let mut parts = s.split(',');
+    let x = parts.next()?.trim().parse().ok()?;
+    let y = parts.next()?.trim().parse().ok()?;
+    let (width, height) = parts.next()?.split_once(['x', 'X'])?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(Region {
+        x,
+        y,
+        width: width.trim().parse().ok()?,
+        height: height.trim().parse().ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::Rule;
+    use crate::sparse::SparseLife;
+    use crate::universe::Universe;
+
+    #[test]
+    fn parses_and_prints_noise() {
+        let noise: Noise = "b0.001/D2=0.5/d3=0.25/@10,-5,30x40".parse().unwrap();
+        assert_eq!(noise.births, [0.001; 9]);
+        assert_eq!(noise.deaths[2..4], [0.5, 0.25]);
+        assert_eq!(
+            noise.regions(),
+            [Region {
+                x: 10,
+                y: -5,
+                width: 30,
+                height: 40
+            }]
+        );
+        assert_eq!(noise.to_string(), "B0.001/D2=0.5/D3=0.25/@10,-5,30x40");
+        assert_eq!(noise.to_string().parse::<Noise>().unwrap(), noise);
+        assert!("".parse::<Noise>().unwrap().is_quiet());
+        for bad in ["B2", "B9=0.1", "X0.1", "@1,2", "D=0.5", "B-0.1"] {
+            assert!(bad.parse::<Noise>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn same_seed_same_noise_within_the_regions() {
+        let noise: Noise = "B0.2/D0.1/@4,4,8x8/@8,8,8x8".parse().unwrap();
+        let run = |seed| {
+            let mut universe = Universe::new(32, 32);
+            let mut rng = Rng::new(seed);
+            for _ in 0..10 {
+                noise.apply(&mut universe, 32, 32, &mut rng);
+            }
+            universe.live_cells()
+        };
+        let cells = run(5);
+        assert!(!cells.is_empty());
+        assert!(cells
+            .iter()
+            .all(|&(x, y, _)| noise.regions().iter().any(|region| region.contains(x, y))));
+        assert_eq!(run(5), cells);
+        assert_ne!(run(6), cells);
+    }
+
+    #[test]
+    fn births_by_neighbour_count() {
+        // Births with two neighbours for certain, and with any other number
+        // never.
+        let noise: Noise = "B2=1".parse().unwrap();
+        let mut life = SparseLife::new(Rule::CONWAY);
+        for x in 0..3 {
+            life.set(x, 0, true);
+        }
+        let flipped = noise.apply(&mut life, 3, 3, &mut Rng::new(0));
+        // Only (0, 1) and (2, 1) of the board have two neighbours.
+        assert_eq!(flipped, 2);
+        assert!(life.get(0, 1) && life.get(2, 1) && !life.get(1, 1));
+    }
+}
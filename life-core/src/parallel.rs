@@ -0,0 +1,25 @@
+/// Splits `cells`, stored as rows of `row_len` items, into one band of whole
+/// rows per thread and calls `f(first_row, band)` on each band.
+///
+/// With `threads <= 1` everything runs on the calling thread, so engines that
+/// never ask for more threads also work where threads cannot be spawned, like
+/// the browser.
+pub(crate) fn for_each_band<T: Send>(
+    cells: &mut [T],
+    row_len: usize,
+    threads: usize,
+    f: impl Fn(usize, &mut [T]) + Sync,
+) {
+    if threads <= 1 || row_len == 0 {
+        f(0, cells);
+        return;
+    }
+    let rows = cells.len() / row_len;
+    let band = rows.div_ceil(threads).max(1);
+    let f = &f;
+    std::thread::scope(|s| {
+        for (i, chunk) in cells.chunks_mut(band * row_len).enumerate() {
+            s.spawn(move || f(i * band, chunk));
+        }
+    });
+}
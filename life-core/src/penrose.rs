@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::graph::Graph;
+use crate::rule::ParseRuleError;
+
+/// The golden ratio, by which each deflation shrinks the tiles.
+const PHI: f64 = 1.618_033_988_749_895;
+
+/// Which of Penrose's aperiodic tilings to lay.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PenroseKind {
+    /// P3: thin rhombs with angles of 36° and 144°, and thick ones with 72°
+    /// and 108°.
+    Rhombs,
+    /// P2: kites and darts.
+    KitesAndDarts,
+}
+
+impl fmt::Display for PenroseKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PenroseKind::Rhombs => write!(f, "P3"),
+            PenroseKind::KitesAndDarts => write!(f, "P2"),
+        }
+    }
+}
+
+impl FromStr for PenroseKind {
+    type Err = ParseRuleError;
+
+    fn from_str(s: &str) -> Result<PenroseKind, ParseRuleError> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "p3" | "rhombs" => Ok(PenroseKind::Rhombs),
+            "p2" | "kites" | "kites-and-darts" => Ok(PenroseKind::KitesAndDarts),
+            _ => Err(ParseRuleError::new(
+                s,
+                "expected P3 (rhombs) or P2 (kites and darts)",
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TileShape {
+    Thin,
+    Thick,
+    Kite,
+    Dart,
+}
+
+/// A tile and its corners in order round it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tile {
+    pub shape: TileShape,
+    pub corners: [[f64; 2]; 4],
+}
+
+impl Tile {
+    /// The mean of the corners.
+    pub fn centre(&self) -> [f64; 2] {
+        let sum = self
+            .corners
+            .iter()
+            .fold([0.0; 2], |s, c| [s[0] + c[0], s[1] + c[1]]);
+        [sum[0] / 4.0, sum[1] / 4.0]
+    }
+}
+
+/// Half a tile: one of Robinson's triangles. `wide` ones have an apex of
+/// 108° and the others one of 36°.
+#[derive(Clone, Copy)]
+struct Triangle {
+    wide: bool,
+    a: [f64; 2],
+    b: [f64; 2],
+    c: [f64; 2],
+}
+
+/// The point `1 / PHI` of the way from `p` to `q`.
+fn towards(p: [f64; 2], q: [f64; 2]) -> [f64; 2] {
+    [p[0] + (q[0] - p[0]) / PHI, p[1] + (q[1] - p[1]) / PHI]
+}
+
+/// A corner as a key, close enough to merge the copies of it that rounding
+/// leaves in neighbouring tiles.
+fn key(p: [f64; 2]) -> (i64, i64) {
+    ((p[0] * 1e6).round() as i64, (p[1] * 1e6).round() as i64)
+}
+
+/// A patch of a Penrose tiling filling the decagon of radius 1 round the
+/// origin, made by deflation.
+///
+/// The patch starts as a wheel of ten Robinson triangles, and each
+/// deflation cuts every triangle into two or three smaller ones, a factor
+/// of the golden ratio smaller. The triangles are then joined in pairs into
+/// tiles: along their bases into rhombs, or along a leg into kites and
+/// darts. Halves left without their other half at the edge of the decagon
+/// are dropped.
+pub struct Penrose {
+    kind: PenroseKind,
+    tiles: Vec<Tile>,
+}
+
+impl Penrose {
+    /// The patch after `deflations` deflations, which multiply the tiles by
+    /// about 2.6 each. Past twenty or so the tiles are too small for `key` to
+    /// tell their corners apart.
+    pub fn new(kind: PenroseKind, deflations: usize) -> Penrose {
+        // In the rhomb tiling the apex is the first corner of each triangle
+        // and in the kite one the second; the other two are where it meets
+        // its other half.
+        let mut triangles: Vec<Triangle> = (0..10)
+            .map(|i| {
+                let corner = |n: i32| {
+                    let angle = n as f64 * std::f64::consts::PI / 10.0;
+                    [angle.cos(), angle.sin()]
+                };
+                let (mut b, mut c) = (corner(2 * i - 1), corner(2 * i + 1));
+                if i % 2 == 0 {
+                    std::mem::swap(&mut b, &mut c);
+                }
+                match kind {
+                    PenroseKind::Rhombs => Triangle {
+                        wide: false,
+                        a: [0.0; 2],
+                        b,
+                        c,
+                    },
+                    PenroseKind::KitesAndDarts => Triangle {
+                        wide: false,
+                        a: b,
+                        b: [0.0; 2],
+                        c,
+                    },
+                }
+            })
+            .collect();
+        for _ in 0..deflations {
+            triangles = triangles.iter().flat_map(|&t| deflate(kind, t)).collect();
+        }
+        // The tiles in the order their second half turns up, which keeps
+        // them in the same order on every run.
+        let mut halves: HashMap<(bool, [(i64, i64); 2]), Triangle> = HashMap::new();
+        let mut tiles = Vec::new();
+        for t in triangles {
+            let mut edge = [key(t.b), key(t.c)];
+            edge.sort();
+            let Some(other) = halves.remove(&(t.wide, edge)) else {
+                halves.insert((t.wide, edge), t);
+                continue;
+            };
+            tiles.push(match (kind, t.wide) {
+                (PenroseKind::Rhombs, wide) => Tile {
+                    shape: if wide {
+                        TileShape::Thick
+                    } else {
+                        TileShape::Thin
+                    },
+                    corners: [t.a, t.b, other.a, t.c],
+                },
+                (PenroseKind::KitesAndDarts, wide) => Tile {
+                    shape: if wide {
+                        TileShape::Dart
+                    } else {
+                        TileShape::Kite
+                    },
+                    corners: [t.b, t.a, t.c, other.a],
+                },
+            });
+        }
+        Penrose { kind, tiles }
+    }
+
+    pub fn kind(&self) -> PenroseKind {
+        self.kind
+    }
+
+    pub fn tiles(&self) -> &[Tile] {
+        &self.tiles
+    }
+
+    /// The tiles as the nodes of a graph, each joined to every tile it
+    /// shares a corner with and drawn at its centre.
+    pub fn graph(&self) -> Graph {
+        let mut graph = Graph::new(self.tiles.len());
+        let mut corners: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+        for (i, tile) in self.tiles.iter().enumerate() {
+            graph.set_position(i, tile.centre());
+            for &corner in &tile.corners {
+                corners.entry(key(corner)).or_default().push(i);
+            }
+        }
+        let mut corners: Vec<_> = corners.into_values().collect();
+        corners.sort();
+        for tiles in corners {
+            for (n, &a) in tiles.iter().enumerate() {
+                for &b in &tiles[n + 1..] {
+                    graph.add_edge(a, b);
+                }
+            }
+        }
+        graph
+    }
+
+    /// The tiling as an SVG image `size` pixels across, live tiles in green
+    /// and the others in a shade for their shape.
+    pub fn svg(&self, size: u32, alive: impl Fn(usize) -> bool) -> String {
+        let mut out = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" \
+             viewBox=\"-1 -1 2 2\">\n<rect x=\"-1\" y=\"-1\" width=\"2\" height=\"2\" fill=\"black\"/>\n",
+            size
+        );
+        for (i, tile) in self.tiles.iter().enumerate() {
+            let fill = match tile.shape {
+                _ if alive(i) => "#32cd32",
+                TileShape::Thin | TileShape::Kite => "#2a2a3a",
+                TileShape::Thick | TileShape::Dart => "#3a3a55",
+            };
+            let points: Vec<String> = tile
+                .corners
+                .iter()
+                .map(|[x, y]| format!("{:.5},{:.5}", x, y))
+                .collect();
+            out += &format!(
+                "<polygon points=\"{}\" fill=\"{}\" stroke=\"black\" stroke-width=\"0.002\"/>\n",
+                points.join(" "),
+                fill
+            );
+        }
+        out += "</svg>\n";
+        out
+    }
+}
+
+/// Cuts a triangle into the two or three that replace it, a factor of the
+/// golden ratio smaller.
+fn deflate(kind: PenroseKind, t: Triangle) -> Vec<Triangle> {
+    let Triangle { a, b, c, wide } = t;
+    let triangle = |wide, a, b, c| Triangle { wide, a, b, c };
+    match (kind, wide) {
+        (PenroseKind::Rhombs, false) => {
+            let p = towards(a, b);
+            vec![triangle(false, c, p, b), triangle(true, p, c, a)]
+        }
+        (PenroseKind::Rhombs, true) => {
+            let q = towards(b, a);
+            let r = towards(b, c);
+            vec![
+                triangle(true, r, c, a),
+                triangle(true, q, r, b),
+                triangle(false, r, q, a),
+            ]
+        }
+        (PenroseKind::KitesAndDarts, false) => {
+            let q = towards(a, b);
+            let r = towards(b, c);
+            vec![
+                triangle(true, r, q, b),
+                triangle(false, q, a, r),
+                triangle(false, c, a, r),
+            ]
+        }
+        (PenroseKind::KitesAndDarts, true) => {
+            let p = towards(c, a);
+            vec![triangle(true, b, p, a), triangle(false, p, c, b)]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn distance(p: [f64; 2], q: [f64; 2]) -> f64 {
+        (p[0] - q[0]).hypot(p[1] - q[1])
+    }
+
+    #[test]
+    fn deflation_makes_proper_tiles_in_the_golden_ratio() {
+        for kind in [PenroseKind::Rhombs, PenroseKind::KitesAndDarts] {
+            let penrose = Penrose::new(kind, 6);
+            let side = |tile: &Tile, i: usize| distance(tile.corners[i], tile.corners[(i + 1) % 4]);
+            let mut counts = HashMap::new();
+            for tile in penrose.tiles() {
+                *counts.entry(tile.shape).or_insert(0.0) += 1.0;
+                let sides = [0, 1, 2, 3].map(|i| side(tile, i));
+                let long = sides.iter().cloned().fold(0.0, f64::max);
+                let short = sides.iter().cloned().fold(f64::MAX, f64::min);
+                match tile.shape {
+                    // Four equal sides.
+                    TileShape::Thin | TileShape::Thick => assert!(long - short < 1e-9),
+                    // Two long and two short, a golden ratio apart.
+                    TileShape::Kite | TileShape::Dart => assert!((long / short - PHI).abs() < 1e-9),
+                }
+            }
+            // Thick tiles outnumber thin ones, and kites darts, by about the
+            // golden ratio.
+            let ratio = match kind {
+                PenroseKind::Rhombs => counts[&TileShape::Thick] / counts[&TileShape::Thin],
+                PenroseKind::KitesAndDarts => counts[&TileShape::Kite] / counts[&TileShape::Dart],
+            };
+            assert!((ratio - PHI).abs() < 0.05, "{} {}", kind, ratio);
+        }
+        assert_eq!(
+            "p2".parse::<PenroseKind>().unwrap(),
+            PenroseKind::KitesAndDarts
+        );
+        assert!("p1".parse::<PenroseKind>().is_err());
+    }
+
+    #[test]
+    fn tiles_sharing_a_corner_are_neighbours() {
+        let penrose = Penrose::new(PenroseKind::Rhombs, 5);
+        let graph = penrose.graph();
+        assert_eq!(graph.len(), penrose.tiles().len());
+        // At most ten tiles meet at a corner of a rhomb tiling, so a tile
+        // has neighbours at each of its four corners and never more than
+        // four times nine.
+        for tile in 0..graph.len() {
+            let degree = graph.neighbours(tile).len();
+            assert!((2..=36).contains(&degree), "{}", degree);
+        }
+        // Tiles in the middle share corners with at least seven others.
+        let middle = (0..graph.len())
+            .min_by(|&a, &b| {
+                let [a, b] = [a, b].map(|t| distance(penrose.tiles()[t].centre(), [0.0; 2]));
+                a.total_cmp(&b)
+            })
+            .unwrap();
+        assert!(graph.neighbours(middle).len() >= 7);
+
+        let svg = penrose.svg(400, |tile| tile == middle);
+        assert_eq!(svg.matches("<polygon").count(), penrose.tiles().len());
+        assert_eq!(svg.matches("#32cd32").count(), 1);
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+#[cfg(test)]
+use std::ops::Range;
+
+#[cfg(test)]
+use crate::engine::Engine;
+
+/// A small seedable random number generator (SplitMix64), so every frontend
+/// draws its soups, colours and spontaneous births from one stream that can be
+/// replayed from the seed.
+///
+/// The same seed gives the same numbers on every platform, so a soup found in
+/// the browser can be run again natively on a board of the same size.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    seed: u64,
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { seed, state: seed }
+    }
+
+    /// A seed that differs from run to run, taken from the keys std picks for
+    /// its hash maps. Where std has no randomness, as in the browser, seed
+    /// from the platform instead.
+    pub fn random_seed() -> u64 {
+        RandomState::new().build_hasher().finish()
+    }
+
+    /// The seed this generator started from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number from `0` up to but not including `n`.
+    pub fn below(&mut self, n: u64) -> u64 {
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+
+    /// A number from 0 up to but not including 1.
+    pub fn gen_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// `true` with probability `p`.
+    pub fn gen_bool(&mut self, p: f64) -> bool {
+        self.gen_f64() < p
+    }
+}
+
+/// Fills the cells in `xs` by `ys` of every engine with the same soup drawn
+/// from `seed`, each cell alive with even odds.
+#[cfg(test)]
+pub(crate) fn soup(seed: u64, xs: Range<i64>, ys: Range<i64>, engines: &mut [&mut dyn Engine]) {
+    let mut rng = Rng::new(seed);
+    for y in ys {
+        for x in xs.clone() {
+            let alive = rng.gen_bool(0.5);
+            for engine in engines.iter_mut() {
+                engine.set(x, y, alive);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let (mut a, mut b) = (Rng::new(42), Rng::new(42));
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert_eq!(a.seed(), 42);
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+
+        let mut rng = Rng::new(7);
+        let heads = (0..10_000).filter(|_| rng.gen_bool(0.25)).count();
+        assert!((2_200..2_800).contains(&heads), "{}", heads);
+        assert!((0..1000).all(|_| rng.below(6) < 6));
+    }
+}
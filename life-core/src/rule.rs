@@ -0,0 +1,583 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::board::Live;
+use crate::hensel::{self, Neighborhoods, CENTRE};
+use crate::lattice::Lattice;
+use crate::topology::Grid;
+
+/// A Life-like (outer totalistic) rule: whether a cell is alive next generation
+/// depends only on whether it is alive now and how many of its neighbours are.
+///
+/// Parsed from the usual notations, e.g. `B3/S23`, `b36/s23`, `23/3` (S/B order)
+/// or `B2/S` (Seeds).
+///
+/// Generations rules add a third part, the number of states, e.g. `/2/3`
+/// (Brian's Brain), `345/2/4` (Star Wars) or `B2/S/C3`. A live cell that does
+/// not survive passes through the dying states before it is dead, and only
+/// live cells count as neighbours.
+///
+/// A trailing `H` makes the rule hexagonal, with 6 neighbours, e.g. `B2/S34H`,
+/// and a trailing `L` triangular, with 12, written `a`, `b` and `c` from 10
+/// up, e.g. `B45/S34L`.
+///
+/// On the square lattice, letters after a count make the rule isotropic
+/// non-totalistic in Hensel's notation: `B2a` is born only with two
+/// neighbours next to each other, `B2-a` with any two but those, as in
+/// `B2-a/S12`.
+///
+/// Colour rules give live cells one of several colours, and a newborn the
+/// colour most of its live neighbours have: `Immigration` is Life with two
+/// colours and `QuadLife` Life with four, and a third part `I` or `Q` does
+/// the same to any other rule, as in `B36/S23/Q`. Only the dense engine keeps
+/// colours; the others run colour rules in a single colour.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rule {
+    /// Bit `n` is set when a dead cell with `n` live neighbours is born,
+    /// under a non-totalistic rule in at least one arrangement.
+    birth: u16,
+    /// Bit `n` is set when a live cell with `n` live neighbours survives,
+    /// under a non-totalistic rule in at least one arrangement.
+    survival: u16,
+    /// 2 for Life-like rules; more for Generations rules.
+    states: u8,
+    /// Number of colours live cells come in, 1 unless this is a colour rule.
+    colors: u8,
+    lattice: Lattice,
+    /// For non-totalistic rules only: bit `n` is set when the cell in the
+    /// middle of neighbourhood `n`, laid out as in Golly, is alive next
+    /// generation.
+    table: Option<Neighborhoods>,
+}
+
+impl Rule {
+    /// Conway's Game of Life, B3/S23.
+    pub const CONWAY: Rule = Rule {
+        birth: 1 << 3,
+        survival: 1 << 2 | 1 << 3,
+        states: 2,
+        colors: 1,
+        lattice: Lattice::Square,
+        table: None,
+    };
+
+    /// Life with two colours.
+    pub const IMMIGRATION: Rule = Rule {
+        colors: 2,
+        ..Rule::CONWAY
+    };
+
+    /// Life with four colours.
+    pub const QUADLIFE: Rule = Rule {
+        colors: 4,
+        ..Rule::CONWAY
+    };
+
+    /// Panics on a count above 12, the most neighbours any lattice has, so
+    /// that the rule can be moved onto a lattice with `with_lattice`.
+    pub fn new(birth: &[u8], survival: &[u8]) -> Rule {
+        let most = Lattice::Triangular.neighbors();
+        assert!(
+            birth.iter().chain(survival).all(|&n| n <= most),
+            "neighbour counts run from 0 to {}",
+            most
+        );
+        let mask = |counts: &[u8]| counts.iter().fold(0u16, |m, &n| m | 1 << n);
+        Rule {
+            birth: mask(birth),
+            survival: mask(survival),
+            states: 2,
+            colors: 1,
+            lattice: Lattice::Square,
+            table: None,
+        }
+    }
+
+    /// The Generations rule with the same birth and survival counts and
+    /// `states` states in all, counting dead and alive.
+    pub fn with_states(self, states: u8) -> Rule {
+        assert!(states >= 2, "a rule needs at least two states");
+        assert!(
+            states == 2 || self.colors == 1,
+            "colour rules have no dying states"
+        );
+        Rule { states, ..self }
+    }
+
+    /// The colour rule with the same counts and `colors` colours, from 1 (no
+    /// colours) to 4.
+    pub fn with_colors(self, colors: u8) -> Rule {
+        assert!(
+            (1..=4).contains(&colors),
+            "colour rules have 1 to 4 colours"
+        );
+        assert!(
+            colors == 1 || self.states == 2,
+            "colour rules have no dying states"
+        );
+        Rule { colors, ..self }
+    }
+
+    /// Number of colours live cells come in, 1 unless this is a colour rule.
+    pub fn colors(&self) -> u8 {
+        self.colors
+    }
+
+    /// The colour, from 1, of a cell born among `parents[c]` live neighbours
+    /// of colour `c + 1`: the colour most of them have, and on a tie the
+    /// first colour none of the tied ones have, which is how QuadLife gives a
+    /// cell with three parents of three colours the fourth. Where every
+    /// colour ties, the first of them.
+    pub fn inherit(&self, parents: [u8; 4]) -> u8 {
+        let colors = &parents[..self.colors as usize];
+        let most = colors.iter().copied().max().unwrap_or(0);
+        let tied = |c: &usize| colors[*c] == most;
+        let color = match (0..colors.len()).filter(tied).count() {
+            1 => (0..colors.len()).find(tied),
+            _ => (0..colors.len())
+                .find(|c| !tied(c))
+                .or_else(|| (0..colors.len()).find(tied)),
+        };
+        color.map_or(1, |c| c as u8 + 1)
+    }
+
+    /// Number of states, 2 unless this is a Generations rule.
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    /// The same rule on another lattice. Only the square lattice has
+    /// non-totalistic rules.
+    pub fn with_lattice(self, lattice: Lattice) -> Rule {
+        assert!(
+            lattice == Lattice::Square || self.is_totalistic(),
+            "non-totalistic rules need the square lattice"
+        );
+        Rule { lattice, ..self }
+    }
+
+    pub fn lattice(&self) -> Lattice {
+        self.lattice
+    }
+
+    /// Whether only the number of live neighbours matters, not where they are.
+    /// Engines that count neighbours can only run these.
+    pub fn is_totalistic(&self) -> bool {
+        self.table.is_none()
+    }
+
+    pub fn is_born(&self, count: u8) -> bool {
+        count < 16 && self.birth & 1 << count != 0
+    }
+
+    pub fn survives(&self, count: u8) -> bool {
+        count < 16 && self.survival & 1 << count != 0
+    }
+
+    /// Parses a rulestring that may end in a Golly bounded-grid suffix, as in
+    /// `B3/S23:T100,80`.
+    pub fn parse_with_grid(s: &str) -> Result<(Rule, Option<Grid>), ParseRuleError> {
+        match s.split_once(':') {
+            Some((rule, grid)) => Ok((rule.parse()?, Some(grid.parse()?))),
+            None => Ok((s.parse()?, None)),
+        }
+    }
+
+    /// Whether a cell is alive next generation, for totalistic rules.
+    pub fn next(&self, alive: bool, count: u8) -> bool {
+        if alive {
+            self.survives(count)
+        } else {
+            self.is_born(count)
+        }
+    }
+
+    /// What a cell is next generation, taking the dying states of
+    /// Generations rules into account.
+    pub fn next_live(&self, live: Live, count: u8) -> Live {
+        self.step(live, self.is_born(count), self.survives(count))
+    }
+
+    /// Like `next_live`, but from the arrangement of the live cells around it,
+    /// with the bit of offset `(dx, dy)` at `(1 - dy) * 3 + (1 - dx)`, as
+    /// non-totalistic rules need.
+    pub fn next_live_around(&self, live: Live, neighborhood: u16) -> Live {
+        let ring = neighborhood & !CENTRE;
+        match &self.table {
+            None => self.next_live(live, ring.count_ones() as u8),
+            Some(table) => self.step(
+                live,
+                hensel::contains(table, ring),
+                hensel::contains(table, ring | CENTRE),
+            ),
+        }
+    }
+
+    fn step(&self, live: Live, born: bool, survives: bool) -> Live {
+        match live {
+            Live::Dead if born => Live::Alive,
+            Live::Alive if survives => Live::Alive,
+            Live::Alive if self.states > 2 => Live::Dying(1),
+            Live::Dying(age) if age + 2 < self.states => Live::Dying(age + 1),
+            _ => Live::Dead,
+        }
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Rule {
+        Rule::CONWAY
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = |mask: u16| -> String {
+            (0..=12)
+                .filter(|n| mask & 1 << n != 0)
+                .map(|n| char::from_digit(n, 13).unwrap())
+                .collect()
+        };
+        // Each count, followed by the letters of the arrangements it applies
+        // to, or of those it does not after a `-`, whichever is shorter.
+        let hensel = |mask: u16, table: &Neighborhoods, alive: bool| -> String {
+            let mut out = String::new();
+            for count in (0..=8u8).filter(|n| mask & 1 << n != 0) {
+                out.push(char::from(b'0' + count));
+                let (with, without): (String, String) =
+                    hensel::letters(count).chars().partition(|&letter| {
+                        let mut class = [0; 8];
+                        hensel::insert_count(&mut class, count, Some(letter), alive);
+                        class.iter().zip(table).all(|(c, t)| c & t == *c)
+                    });
+                if without.is_empty() {
+                    continue;
+                } else if with.len() <= without.len() {
+                    out.push_str(&with);
+                } else {
+                    out.push('-');
+                    out.push_str(&without);
+                }
+            }
+            out
+        };
+        match self.colors {
+            2 if *self == Rule::IMMIGRATION => return write!(f, "Immigration"),
+            4 if *self == Rule::QUADLIFE => return write!(f, "QuadLife"),
+            _ => {}
+        }
+        match &self.table {
+            None => write!(f, "B{}/S{}", digits(self.birth), digits(self.survival))?,
+            Some(table) => write!(
+                f,
+                "B{}/S{}",
+                hensel(self.birth, table, false),
+                hensel(self.survival, table, true)
+            )?,
+        }
+        if self.states > 2 {
+            write!(f, "/C{}", self.states)?;
+        }
+        match self.colors {
+            2 => write!(f, "/I")?,
+            4 => write!(f, "/Q")?,
+            _ => {}
+        }
+        match self.lattice {
+            Lattice::Square => Ok(()),
+            Lattice::Hexagonal => write!(f, "H"),
+            Lattice::Triangular => write!(f, "L"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseRuleError {
+    rule: String,
+    reason: &'static str,
+}
+
+impl ParseRuleError {
+    pub(crate) fn new(rule: &str, reason: &'static str) -> ParseRuleError {
+        ParseRuleError {
+            rule: rule.to_string(),
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for ParseRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid rule `{}`: {}", self.rule, self.reason)
+    }
+}
+
+impl std::error::Error for ParseRuleError {}
+
+/// Parses the number of states of a Generations rule, e.g. the `4` of `C4`.
+fn parse_states(rule: &str, digits: &str) -> Result<u8, ParseRuleError> {
+    match digits.parse::<u8>() {
+        Ok(n) if n >= 2 => Ok(n),
+        _ => Err(ParseRuleError::new(
+            rule,
+            "states must be a number from 2 to 255",
+        )),
+    }
+}
+
+/// Parses the neighbour counts of one half of a rulestring, e.g. the `36` of
+/// `B36` or the `2-a` of `B2-a`, adding the neighbourhoods they stand for, with
+/// the cell itself `alive` or not, to `table`.
+fn parse_counts(
+    rule: &str,
+    digits: &str,
+    lattice: Lattice,
+    alive: bool,
+    table: &mut Neighborhoods,
+) -> Result<u16, ParseRuleError> {
+    let mut mask = 0;
+    if lattice != Lattice::Square {
+        for ch in digits.chars() {
+            match ch.to_digit(13) {
+                Some(n) if n <= lattice.neighbors() as u32 => mask |= 1 << n,
+                _ => {
+                    return Err(ParseRuleError::new(
+                        rule,
+                        match lattice {
+                            Lattice::Hexagonal => "counts must be digits 0-6",
+                            _ => "counts must be digits 0-9 or a-c",
+                        },
+                    ))
+                }
+            }
+        }
+        return Ok(mask);
+    }
+
+    let mut chars = digits.chars().peekable();
+    while let Some(ch) = chars.next() {
+        let count = match ch.to_digit(10) {
+            Some(n @ 0..=8) => n as u8,
+            _ => return Err(ParseRuleError::new(rule, "counts must be digits 0-8")),
+        };
+        let negated = chars.next_if_eq(&'-').is_some();
+        let mut picked = [0; 8];
+        let mut letters = 0;
+        while let Some(letter) = chars.next_if(char::is_ascii_alphabetic) {
+            if !hensel::letters(count).contains(letter) {
+                return Err(ParseRuleError::new(
+                    rule,
+                    "unknown arrangement letter for its count",
+                ));
+            }
+            hensel::insert_count(&mut picked, count, Some(letter), alive);
+            letters += 1;
+        }
+        if letters == 0 || negated {
+            if letters == 0 && negated {
+                return Err(ParseRuleError::new(rule, "expected letters after `-`"));
+            }
+            let mut all = [0; 8];
+            hensel::insert_count(&mut all, count, None, alive);
+            for (picked, all) in picked.iter_mut().zip(all) {
+                *picked = if negated { all & !*picked } else { all };
+            }
+        }
+        if picked != [0; 8] {
+            mask |= 1 << count;
+        }
+        for (table, picked) in table.iter_mut().zip(picked) {
+            *table |= picked;
+        }
+    }
+    Ok(mask)
+}
+
+/// The table of the totalistic rule with these counts.
+fn totalistic_table(birth: u16, survival: u16) -> Neighborhoods {
+    let mut table = [0; 8];
+    for count in 0..=8 {
+        if birth & 1 << count != 0 {
+            hensel::insert_count(&mut table, count, None, false);
+        }
+        if survival & 1 << count != 0 {
+            hensel::insert_count(&mut table, count, None, true);
+        }
+    }
+    table
+}
+
+impl FromStr for Rule {
+    type Err = ParseRuleError;
+
+    fn from_str(s: &str) -> Result<Rule, ParseRuleError> {
+        if s.trim().eq_ignore_ascii_case("Immigration") {
+            return Ok(Rule::IMMIGRATION);
+        }
+        if s.trim().eq_ignore_ascii_case("QuadLife") {
+            return Ok(Rule::QUADLIFE);
+        }
+        let (rule, lattice) = match s.trim() {
+            r if r.ends_with(['H', 'h']) => (&r[..r.len() - 1], Lattice::Hexagonal),
+            r if r.ends_with(['L', 'l']) => (&r[..r.len() - 1], Lattice::Triangular),
+            r => (r, Lattice::Square),
+        };
+        let parts: Vec<&str> = rule.split('/').collect();
+        if parts.len() != 2 && parts.len() != 3 {
+            return Err(ParseRuleError::new(
+                s,
+                "expected two or three parts separated by `/`",
+            ));
+        }
+        let (mut birth, mut survival, mut states) = (None, None, None);
+        let mut colors = 1;
+        let mut table = [0; 8];
+        for (i, part) in parts.iter().enumerate() {
+            let mut chars = part.chars();
+            let mut counts = |digits: &str, alive: bool| {
+                parse_counts(s, digits, lattice, alive, &mut table).map(Some)
+            };
+            match chars.next() {
+                Some('B' | 'b') => birth = counts(chars.as_str(), false)?,
+                Some('S' | 's') => survival = counts(chars.as_str(), true)?,
+                Some('C' | 'c') => states = Some(parse_states(s, chars.as_str())?),
+                Some('I' | 'i') if i == 2 && chars.as_str().is_empty() => colors = 2,
+                Some('Q' | 'q') if i == 2 && chars.as_str().is_empty() => colors = 4,
+                // Without letters the old notation is survival/birth/states.
+                _ if i == 0 => survival = counts(part, true)?,
+                _ if i == 1 => birth = counts(part, false)?,
+                _ => states = Some(parse_states(s, part)?),
+            }
+        }
+        match (birth, survival) {
+            (Some(birth), Some(survival)) => Ok(Rule {
+                birth,
+                survival,
+                states: states.unwrap_or(2),
+                colors,
+                lattice,
+                // Letters that add up to whole counts still make a totalistic rule.
+                table: Some(table).filter(|table| {
+                    lattice == Lattice::Square && *table != totalistic_table(birth, survival)
+                }),
+            }),
+            _ => Err(ParseRuleError::new(
+                s,
+                "expected one birth and one survival part",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "neighbour counts run from 0 to 12")]
+    fn rejects_counts_beyond_any_lattice() {
+        Rule::new(&[3], &[16]);
+    }
+
+    #[test]
+    fn parses_common_notations() {
+        assert_eq!("B3/S23".parse(), Ok(Rule::CONWAY));
+        assert_eq!("23/3".parse(), Ok(Rule::CONWAY));
+        assert_eq!("s23/b3".parse(), Ok(Rule::CONWAY));
+        assert_eq!("B36/S23".parse(), Ok(Rule::new(&[3, 6], &[2, 3])));
+        assert_eq!("B2/S".parse(), Ok(Rule::new(&[2], &[])));
+        assert_eq!(
+            Rule::new(&[3, 6, 7, 8], &[3, 4, 6, 7, 8]).to_string(),
+            "B3678/S34678"
+        );
+    }
+
+    #[test]
+    fn parses_generations() {
+        let brain = Rule::new(&[2], &[]).with_states(3);
+        assert_eq!("/2/3".parse(), Ok(brain));
+        assert_eq!("B2/S/C3".parse(), Ok(brain));
+        assert_eq!(brain.to_string(), "B2/S/C3");
+        let star_wars: Rule = "345/2/4".parse().unwrap();
+        assert_eq!(star_wars, Rule::new(&[2], &[3, 4, 5]).with_states(4));
+        assert_eq!(star_wars.next_live(Live::Alive, 1), Live::Dying(1));
+        assert_eq!(star_wars.next_live(Live::Dying(1), 2), Live::Dying(2));
+        assert_eq!(star_wars.next_live(Live::Dying(2), 2), Live::Dead);
+        assert!("B2/S/C1".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn parses_colour_rules_and_inherits() {
+        assert_eq!("immigration".parse(), Ok(Rule::IMMIGRATION));
+        assert_eq!("B3/S23/Q".parse(), Ok(Rule::QUADLIFE));
+        assert_eq!(Rule::QUADLIFE.to_string(), "QuadLife");
+        let hex: Rule = "B2/S34/IH".parse().unwrap();
+        assert_eq!(hex.colors(), 2);
+        assert_eq!(hex.to_string(), "B2/S34/IH");
+        assert!("B3/S23/X".parse::<Rule>().is_err());
+
+        // The majority of the parents, or in QuadLife the colour none of
+        // three different ones has.
+        assert_eq!(Rule::IMMIGRATION.inherit([1, 2, 0, 0]), 2);
+        assert_eq!(Rule::QUADLIFE.inherit([2, 0, 0, 1]), 1);
+        assert_eq!(Rule::QUADLIFE.inherit([1, 1, 0, 1]), 3);
+        assert_eq!(Rule::QUADLIFE.inherit([0, 1, 1, 1]), 1);
+        assert_eq!(Rule::IMMIGRATION.inherit([1, 1, 0, 0]), 1);
+    }
+
+    #[test]
+    fn parses_lattice_suffixes() {
+        let hex: Rule = "B2/S34H".parse().unwrap();
+        assert_eq!(
+            hex,
+            Rule::new(&[2], &[3, 4]).with_lattice(Lattice::Hexagonal)
+        );
+        assert_eq!(hex.to_string(), "B2/S34H");
+        let tri: Rule = "B45/S34abL".parse().unwrap();
+        assert_eq!(tri.lattice(), Lattice::Triangular);
+        assert!(tri.survives(11) && !tri.survives(12));
+        assert_eq!(tri.to_string(), "B45/S34abL");
+        assert_eq!(
+            "345/2/4H".parse(),
+            Ok(Rule::new(&[2], &[3, 4, 5])
+                .with_states(4)
+                .with_lattice(Lattice::Hexagonal))
+        );
+        assert!("B7/S34H".parse::<Rule>().is_err());
+        assert!("B3a/S23".parse::<Rule>().is_ok());
+        assert!("B3x/S23".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn parses_hensel_notation() {
+        let rule: Rule = "B2-a/S12".parse().unwrap();
+        assert!(!rule.is_totalistic());
+        assert_eq!(rule.to_string(), "B2-a/S12");
+        assert_eq!("b2cekin/s12".parse(), Ok(rule));
+        assert_eq!(
+            "B35y/S1e2-ci3-a".parse::<Rule>().unwrap().to_string(),
+            "B35y/S1e2-ci3-a"
+        );
+        // Listing every letter is the same as none.
+        assert_eq!("B3/S2ceaikn3".parse(), Ok(Rule::CONWAY));
+        assert!("B2a/S1-".parse::<Rule>().is_err());
+        assert!("B1a/S".parse::<Rule>().is_err());
+
+        let (n, ne, e) = (hensel::bit(0, -1), hensel::bit(1, -1), hensel::bit(1, 0));
+        // Two neighbours next to each other are the `a` arrangement.
+        assert_eq!(rule.next_live_around(Live::Dead, n | ne), Live::Dead);
+        assert_eq!(rule.next_live_around(Live::Dead, n | e), Live::Alive);
+        assert_eq!(
+            Rule::CONWAY.next_live_around(Live::Alive, n | e | CENTRE),
+            Live::Alive
+        );
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!("B9/S23".parse::<Rule>().is_err());
+        assert!("B3".parse::<Rule>().is_err());
+        assert!("B3/B4".parse::<Rule>().is_err());
+        assert!("B3/S23/C3/C4".parse::<Rule>().is_err());
+    }
+}
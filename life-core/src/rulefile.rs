@@ -0,0 +1,862 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::board::Color;
+use crate::engine::Engine;
+use crate::ltl::Neighborhood;
+use crate::parallel::for_each_band;
+use crate::rule::ParseRuleError;
+use crate::topology::Topology;
+
+/// A set of states, one bit each.
+type States = [u64; 4];
+
+fn single(state: u8) -> States {
+    let mut set = [0; 4];
+    set[state as usize / 64] |= 1 << (state % 64);
+    set
+}
+
+fn holds(set: &States, state: u8) -> bool {
+    set[state as usize / 64] >> (state % 64) & 1 == 1
+}
+
+/// What one cell of a transition must be.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Input {
+    states: States,
+    /// A named variable: every input bound to the same one takes the same
+    /// state, as in Golly.
+    var: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Output {
+    State(u8),
+    /// The state the input bound to this variable matched.
+    Var(usize),
+}
+
+/// One line of a `@TABLE`: the cell itself, then its neighbours in Golly's
+/// order, clockwise from north.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Transition {
+    inputs: Vec<Input>,
+    output: Output,
+}
+
+/// Variables bound so far while matching a transition, with their states.
+type Bound = Vec<(usize, u8)>;
+
+impl Transition {
+    /// The next state when the transition applies to `cell` and `neighbours`,
+    /// taken in the order the inputs are in.
+    fn apply(&self, cell: u8, neighbours: &[u8]) -> Option<u8> {
+        let mut bound = Vec::new();
+        let cells = std::iter::once(&cell).chain(neighbours);
+        for (input, &state) in self.inputs.iter().zip(cells) {
+            if !Transition::accepts(input, state, &mut bound) {
+                return None;
+            }
+        }
+        Some(self.output(&bound))
+    }
+
+    /// Like `apply`, but the neighbours may match the inputs in any order.
+    fn apply_permuted(&self, cell: u8, neighbours: &[u8]) -> Option<u8> {
+        let mut bound = Vec::new();
+        if !Transition::accepts(&self.inputs[0], cell, &mut bound) {
+            return None;
+        }
+        let mut used = vec![false; neighbours.len()];
+        self.assign(1, neighbours, &mut used, &mut bound)
+            .then(|| self.output(&bound))
+    }
+
+    /// Finds an unused neighbour for each input from `i` on, backtracking.
+    fn assign(&self, i: usize, neighbours: &[u8], used: &mut [bool], bound: &mut Bound) -> bool {
+        if i == self.inputs.len() {
+            return true;
+        }
+        for j in 0..neighbours.len() {
+            if used[j] {
+                continue;
+            }
+            let before = bound.len();
+            if Transition::accepts(&self.inputs[i], neighbours[j], bound) {
+                used[j] = true;
+                if self.assign(i + 1, neighbours, used, bound) {
+                    return true;
+                }
+                used[j] = false;
+            }
+            bound.truncate(before);
+        }
+        false
+    }
+
+    fn accepts(input: &Input, state: u8, bound: &mut Bound) -> bool {
+        if !holds(&input.states, state) {
+            return false;
+        }
+        let Some(var) = input.var else {
+            return true;
+        };
+        match bound.iter().find(|&&(v, _)| v == var) {
+            Some(&(_, value)) => value == state,
+            None => {
+                bound.push((var, state));
+                true
+            }
+        }
+    }
+
+    fn output(&self, bound: &Bound) -> u8 {
+        match self.output {
+            Output::State(state) => state,
+            Output::Var(var) => bound.iter().find(|&&(v, _)| v == var).unwrap().1,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Transitions {
+    /// Tried in order; the first to apply wins, and a cell none applies to
+    /// stays as it is.
+    Table {
+        transitions: Vec<Transition>,
+        permute: bool,
+    },
+    /// Golly's decision tree: from the root, each node is indexed by the
+    /// state of the next input, down to the new state.
+    Tree { nodes: Vec<Vec<u32>>, root: usize },
+}
+
+/// A rule in Golly's `.rule` format, with its transitions given by a `@TABLE`
+/// or a `@TREE` and the colour of each state by `@COLORS`.
+///
+/// Moore and von Neumann neighbourhoods are supported, with the table
+/// symmetries `none`, `rotate4`, `rotate8`, `reflect_horizontal`,
+/// `rotate4reflect`, `rotate8reflect` and `permute`. Other sections, such as
+/// `@ICONS`, are skipped.
+#[derive(Clone, Debug)]
+pub struct RuleFile {
+    name: String,
+    states: u8,
+    neighborhood: Neighborhood,
+    transitions: Transitions,
+    colors: Vec<Color>,
+}
+
+impl RuleFile {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Number of states, counting the empty state 0.
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    /// The colour of `state`, from `@COLORS` or else Golly's default: black
+    /// for 0 and red through yellow for the others.
+    pub fn color(&self, state: u8) -> Color {
+        self.colors[state as usize]
+    }
+
+    /// The next state of a cell in state `cell`, with `neighbours` in Golly's
+    /// table order: N, NE, E, SE, S, SW, W, NW for Moore and N, E, S, W for
+    /// von Neumann.
+    pub fn next(&self, cell: u8, neighbours: &[u8]) -> u8 {
+        match &self.transitions {
+            Transitions::Table {
+                transitions,
+                permute,
+            } => transitions
+                .iter()
+                .find_map(|t| match permute {
+                    true => t.apply_permuted(cell, neighbours),
+                    false => t.apply(cell, neighbours),
+                })
+                .unwrap_or(cell),
+            Transitions::Tree { nodes, root } => {
+                // Trees take the neighbours in a different order.
+                let order: &[usize] = match self.neighborhood {
+                    Neighborhood::Moore => &[7, 1, 5, 3, 0, 6, 2, 4],
+                    Neighborhood::VonNeumann => &[0, 3, 1, 2],
+                };
+                let mut node = *root;
+                for &i in order {
+                    node = nodes[node][neighbours[i] as usize] as usize;
+                }
+                nodes[node][cell as usize] as u8
+            }
+        }
+    }
+}
+
+/// The permutations of the neighbours a symmetry stands for, each listing
+/// where every position of the written transition moves to.
+fn symmetries(symmetry: &str, neighbours: usize) -> Option<Vec<Vec<usize>>> {
+    let rotate = |k: usize| (0..neighbours).map(|i| (i + k) % neighbours).collect();
+    let reflect = |p: &Vec<usize>| -> Vec<usize> {
+        p.iter().map(|&i| (neighbours - i) % neighbours).collect()
+    };
+    let quarter = neighbours / 4;
+    let mut permutations: Vec<Vec<usize>> = match symmetry {
+        "none" | "permute" => vec![rotate(0)],
+        "rotate4" | "rotate4reflect" => (0..4).map(|k| rotate(k * quarter)).collect(),
+        "rotate8" | "rotate8reflect" if neighbours == 8 => (0..8).map(rotate).collect(),
+        "reflect_horizontal" => vec![rotate(0), reflect(&rotate(0))],
+        _ => return None,
+    };
+    if symmetry.ends_with("reflect") {
+        let reflected: Vec<Vec<usize>> = permutations.iter().map(reflect).collect();
+        permutations.extend(reflected);
+    }
+    Some(permutations)
+}
+
+/// Splits a transition into its entries: separated by commas or spaces, with
+/// `{...}` kept whole, or one digit each when written without separators.
+fn entries(line: &str, count: usize) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut entry = String::new();
+    let mut depth = 0;
+    for ch in line.chars() {
+        match ch {
+            '{' => {
+                depth += 1;
+                entry.push(ch);
+            }
+            '}' => {
+                depth -= 1;
+                entry.push(ch);
+            }
+            ',' | ' ' | '\t' if depth == 0 => {
+                if !entry.is_empty() {
+                    out.push(std::mem::take(&mut entry));
+                }
+            }
+            _ => entry.push(ch),
+        }
+    }
+    if !entry.is_empty() {
+        out.push(entry);
+    }
+    if out.len() == 1 && out[0].len() == count && out[0].chars().all(|c| c.is_ascii_digit()) {
+        out = out[0].chars().map(String::from).collect();
+    }
+    out
+}
+
+/// Parses a `@TABLE` section.
+fn parse_table(lines: &[&str]) -> Result<(u8, Neighborhood, Transitions), ParseRuleError> {
+    let (mut states, mut neighborhood, mut symmetry) = (None, Neighborhood::Moore, "none");
+    let mut vars: HashMap<String, (usize, States)> = HashMap::new();
+    let mut transitions = Vec::new();
+    for &line in lines {
+        let line = line.trim();
+        if let Some((key, value)) = line.split_once(':') {
+            let value = value.trim();
+            match key.trim() {
+                "n_states" => match value.parse::<u8>() {
+                    Ok(n @ 2..) => states = Some(n - 1),
+                    _ => return Err(ParseRuleError::new(line, "n_states must be 2 to 255")),
+                },
+                "neighborhood" => {
+                    neighborhood = match value {
+                        "Moore" => Neighborhood::Moore,
+                        "vonNeumann" => Neighborhood::VonNeumann,
+                        _ => {
+                            return Err(ParseRuleError::new(
+                                line,
+                                "only Moore and vonNeumann neighbourhoods are supported",
+                            ))
+                        }
+                    }
+                }
+                "symmetries" => symmetry = value,
+                _ => return Err(ParseRuleError::new(line, "unknown setting")),
+            }
+            continue;
+        }
+        // Both settings come before any variable or transition.
+        let last = states.ok_or_else(|| ParseRuleError::new(line, "missing n_states"))?;
+        let neighbours = match neighborhood {
+            Neighborhood::Moore => 8,
+            Neighborhood::VonNeumann => 4,
+        };
+        // The states an entry, a variable's elements or a set, stands for.
+        let parse_set = |entry: &str| -> Result<(States, Option<usize>), ParseRuleError> {
+            let entry = entry.trim();
+            if let Some(inner) = entry.strip_prefix('{').and_then(|e| e.strip_suffix('}')) {
+                let mut set = [0; 4];
+                for element in inner.split(',') {
+                    let element = element.trim();
+                    let part = match vars.get(element) {
+                        Some(&(_, states)) => states,
+                        None => match element.parse::<u8>() {
+                            Ok(state) if state <= last => single(state),
+                            _ => return Err(ParseRuleError::new(entry, "unknown state")),
+                        },
+                    };
+                    for (set, part) in set.iter_mut().zip(part) {
+                        *set |= part;
+                    }
+                }
+                return Ok((set, None));
+            }
+            match (vars.get(entry), entry.parse::<u8>()) {
+                (Some(&(id, states)), _) => Ok((states, Some(id))),
+                (None, Ok(state)) if state <= last => Ok((single(state), None)),
+                _ => Err(ParseRuleError::new(entry, "unknown state or variable")),
+            }
+        };
+
+        if let Some(declaration) = line.strip_prefix("var ") {
+            let (name, set) = declaration
+                .split_once('=')
+                .ok_or_else(|| ParseRuleError::new(line, "expected var name={...}"))?;
+            let (states, _) = parse_set(set)?;
+            let id = vars.len();
+            vars.insert(name.trim().to_string(), (id, states));
+            continue;
+        }
+
+        let entries = entries(line, neighbours + 2);
+        if entries.len() != neighbours + 2 {
+            return Err(ParseRuleError::new(
+                line,
+                "wrong number of entries for the neighbourhood",
+            ));
+        }
+        let mut inputs = Vec::new();
+        for entry in &entries[..neighbours + 1] {
+            let (states, var) = parse_set(entry)?;
+            inputs.push(Input { states, var });
+        }
+        let output = match parse_set(&entries[neighbours + 1])? {
+            (_, Some(var)) if inputs.iter().any(|i| i.var == Some(var)) => Output::Var(var),
+            (set, None) if set.iter().map(|w| w.count_ones()).sum::<u32>() == 1 => {
+                Output::State((0..=last).find(|&s| holds(&set, s)).unwrap())
+            }
+            _ => {
+                return Err(ParseRuleError::new(
+                    line,
+                    "the new state must be a state or a variable used before it",
+                ))
+            }
+        };
+        let transition = Transition { inputs, output };
+        let permutations = symmetries(symmetry, neighbours)
+            .ok_or_else(|| ParseRuleError::new(symmetry, "unknown symmetries"))?;
+        for permutation in permutations {
+            let mut inputs = transition.inputs.clone();
+            for (i, &to) in permutation.iter().enumerate() {
+                inputs[to + 1] = transition.inputs[i + 1].clone();
+            }
+            let moved = Transition {
+                inputs,
+                output: transition.output,
+            };
+            if !transitions.contains(&moved) {
+                transitions.push(moved);
+            }
+        }
+    }
+    let last = states.ok_or_else(|| ParseRuleError::new("@TABLE", "missing n_states"))?;
+    Ok((
+        last,
+        neighborhood,
+        Transitions::Table {
+            transitions,
+            permute: symmetry == "permute",
+        },
+    ))
+}
+
+/// Parses a `@TREE` section.
+fn parse_tree(lines: &[&str]) -> Result<(u8, Neighborhood, Transitions), ParseRuleError> {
+    let (mut states, mut neighborhood) = (None, None);
+    let mut nodes: Vec<Vec<u32>> = Vec::new();
+    let mut levels: Vec<u32> = Vec::new();
+    for &line in lines {
+        let line = line.trim();
+        if let Some((key, value)) = line.split_once('=') {
+            let value = value.trim();
+            match key.trim() {
+                "num_states" => match value.parse::<u8>() {
+                    Ok(n @ 2..) => states = Some(n - 1),
+                    _ => return Err(ParseRuleError::new(line, "num_states must be 2 to 255")),
+                },
+                "num_neighbors" => {
+                    neighborhood = match value {
+                        "8" => Some(Neighborhood::Moore),
+                        "4" => Some(Neighborhood::VonNeumann),
+                        _ => return Err(ParseRuleError::new(line, "num_neighbors must be 4 or 8")),
+                    }
+                }
+                "num_nodes" => {}
+                _ => return Err(ParseRuleError::new(line, "unknown setting")),
+            }
+            continue;
+        }
+        let numbers: Result<Vec<u32>, _> = line.split_whitespace().map(str::parse).collect();
+        let numbers = numbers.map_err(|_| ParseRuleError::new(line, "expected numbers"))?;
+        let (level, children) = numbers
+            .split_first()
+            .ok_or_else(|| ParseRuleError::new(line, "empty node"))?;
+        let Some(last) = states else {
+            return Err(ParseRuleError::new(line, "missing num_states"));
+        };
+        if children.len() != last as usize + 1 {
+            return Err(ParseRuleError::new(
+                line,
+                "expected a child for every state",
+            ));
+        }
+        // Above level 1, children are earlier nodes a level down; at level
+        // 1, new states.
+        let fits = |c: u32| match *level {
+            0 => false,
+            1 => c <= last as u32,
+            _ => levels.get(c as usize) == Some(&(level - 1)),
+        };
+        if !children.iter().all(|&c| fits(c)) {
+            return Err(ParseRuleError::new(
+                line,
+                "children must be nodes a level down, or states at level 1",
+            ));
+        }
+        nodes.push(children.to_vec());
+        levels.push(*level);
+    }
+    match (states, neighborhood) {
+        (Some(last), Some(neighborhood)) if !nodes.is_empty() => {
+            // The root takes the cell and each of its neighbours in turn.
+            let neighbors = match neighborhood {
+                Neighborhood::Moore => 8,
+                Neighborhood::VonNeumann => 4,
+            };
+            if levels.last() != Some(&(neighbors + 1)) {
+                return Err(ParseRuleError::new(
+                    "@TREE",
+                    "the last node must be the root, a level above num_neighbors",
+                ));
+            }
+            let root = nodes.len() - 1;
+            Ok((last, neighborhood, Transitions::Tree { nodes, root }))
+        }
+        _ => Err(ParseRuleError::new(
+            "@TREE",
+            "expected num_states, num_neighbors and nodes",
+        )),
+    }
+}
+
+impl FromStr for RuleFile {
+    type Err = ParseRuleError;
+
+    fn from_str(s: &str) -> Result<RuleFile, ParseRuleError> {
+        // Group the lines under the section they follow, dropping comments.
+        let mut sections: Vec<(&str, Vec<&str>)> = Vec::new();
+        for line in s.lines() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            match line.strip_prefix('@') {
+                Some(header) => sections.push((header, Vec::new())),
+                None => match sections.last_mut() {
+                    Some((_, lines)) => lines.push(line),
+                    None => return Err(ParseRuleError::new(line, "expected @RULE first")),
+                },
+            }
+        }
+        let name = match sections.first() {
+            Some((header, _)) if header.starts_with("RULE") => header[4..].trim().to_string(),
+            _ => return Err(ParseRuleError::new(s, "expected @RULE first")),
+        };
+
+        let mut rule = None;
+        let mut colors = Vec::new();
+        for (header, lines) in &sections {
+            match header.split_whitespace().next() {
+                Some("TABLE") => rule = Some(parse_table(lines)?),
+                Some("TREE") => rule = Some(parse_tree(lines)?),
+                Some("COLORS") => colors = lines.clone(),
+                _ => {}
+            }
+        }
+        let (last, neighborhood, transitions) =
+            rule.ok_or_else(|| ParseRuleError::new(&name, "expected @TABLE or @TREE"))?;
+
+        let mut palette: Vec<Color> = (0..=last as usize)
+            .map(|state| match state {
+                0 => [0.0, 0.0, 0.0, 1.0],
+                _ => {
+                    let t = (state - 1) as f32 / (last as f32 - 1.0).max(1.0);
+                    [1.0, t, 0.0, 1.0]
+                }
+            })
+            .collect();
+        for line in colors {
+            let numbers: Result<Vec<u32>, _> = line.split_whitespace().map(str::parse).collect();
+            let rgb =
+                |r: u32, g: u32, b: u32| [r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0];
+            match numbers.as_deref() {
+                Ok(&[state, r, g, b]) if state <= last as u32 => {
+                    let [r, g, b] = rgb(r, g, b);
+                    palette[state as usize] = [r, g, b, 1.0];
+                }
+                // A gradient across a range of states.
+                Ok(&[first, end, r0, g0, b0, r1, g1, b1]) if first <= end && end <= last as u32 => {
+                    let (from, to) = (rgb(r0, g0, b0), rgb(r1, g1, b1));
+                    for state in first..=end {
+                        let t = (state - first) as f32 / (end - first).max(1) as f32;
+                        let mix = |i: usize| from[i] + (to[i] - from[i]) * t;
+                        palette[state as usize] = [mix(0), mix(1), mix(2), 1.0];
+                    }
+                }
+                _ => return Err(ParseRuleError::new(line, "expected state r g b")),
+            }
+        }
+
+        Ok(RuleFile {
+            name,
+            states: last + 1,
+            neighborhood,
+            transitions,
+            colors: palette,
+        })
+    }
+}
+
+/// A finite board run under a `RuleFile`, one byte of state per cell.
+///
+/// Each band of rows remembers what every neighbourhood it met during a tick
+/// turned into, as searching a table is slow and patterns keep meeting the
+/// same few neighbourhoods.
+pub struct RuleLoader {
+    width: usize,
+    height: usize,
+    topology: Topology,
+    rule: RuleFile,
+    cells: Vec<u8>,
+    next: Vec<u8>,
+    now: u64,
+    threads: usize,
+}
+
+impl RuleLoader {
+    pub fn new(width: usize, height: usize, rule: RuleFile) -> RuleLoader {
+        RuleLoader::with_topology(width, height, rule, Topology::Plane)
+    }
+
+    pub fn with_topology(
+        width: usize,
+        height: usize,
+        rule: RuleFile,
+        topology: Topology,
+    ) -> RuleLoader {
+        RuleLoader {
+            width,
+            height,
+            topology,
+            rule,
+            cells: vec![0; width * height],
+            next: vec![0; width * height],
+            now: 0,
+            threads: 1,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn rule(&self) -> &RuleFile {
+        &self.rule
+    }
+
+    /// Splits each tick into bands of rows stepped on `threads` threads. The
+    /// result is the same for any number of threads.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    /// The cell at `(w, h)` followed by its neighbours in table order.
+    fn neighborhood(&self, w: usize, h: usize) -> [u8; 9] {
+        let offsets: &[(i64, i64)] = match self.rule.neighborhood {
+            Neighborhood::Moore => &[
+                (0, -1),
+                (1, -1),
+                (1, 0),
+                (1, 1),
+                (0, 1),
+                (-1, 1),
+                (-1, 0),
+                (-1, -1),
+            ],
+            Neighborhood::VonNeumann => &[(0, -1), (1, 0), (0, 1), (-1, 0)],
+        };
+        let mut out = [0; 9];
+        out[0] = self.cells[h * self.width + w];
+        for (out, &(dx, dy)) in out[1..].iter_mut().zip(offsets) {
+            *out = self.state(w as i64 + dx, h as i64 + dy);
+        }
+        out
+    }
+}
+
+impl Engine for RuleLoader {
+    fn tick(&mut self) {
+        self.now += 1;
+        let neighbours = match self.rule.neighborhood {
+            Neighborhood::Moore => 8,
+            Neighborhood::VonNeumann => 4,
+        };
+        let mut next = std::mem::take(&mut self.next);
+        let this = &*self;
+        for_each_band(&mut next, self.width, self.threads, |first, rows| {
+            let mut seen: HashMap<[u8; 9], u8> = HashMap::new();
+            for (h, row) in (first..).zip(rows.chunks_mut(this.width)) {
+                for (w, cell) in row.iter_mut().enumerate() {
+                    let n = this.neighborhood(w, h);
+                    *cell = *seen
+                        .entry(n)
+                        .or_insert_with(|| this.rule.next(n[0], &n[1..=neighbours]));
+                }
+            }
+        });
+        self.next = std::mem::replace(&mut self.cells, next);
+    }
+
+    fn generation(&self) -> u64 {
+        self.now
+    }
+
+    fn set_generation(&mut self, generation: u64) {
+        self.now = generation;
+    }
+
+    /// Number of cells in any state but 0.
+    fn population(&self) -> u64 {
+        self.cells.iter().filter(|&&state| state != 0).count() as u64
+    }
+
+    fn get(&self, x: i64, y: i64) -> bool {
+        self.state(x, y) != 0
+    }
+
+    fn state(&self, x: i64, y: i64) -> u8 {
+        self.topology
+            .locate(x, y, self.width, self.height)
+            .map_or(0, |(w, h)| self.cells[h * self.width + w])
+    }
+
+    fn set(&mut self, x: i64, y: i64, alive: bool) {
+        self.set_state(x, y, alive as u8)
+    }
+
+    fn set_state(&mut self, x: i64, y: i64, state: u8) {
+        assert!(
+            state < self.rule.states,
+            "{} has no state {}",
+            self.rule.name,
+            state
+        );
+        if let Some((w, h)) = self.topology.locate(x, y, self.width, self.height) {
+            self.cells[h * self.width + w] = state;
+        }
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64, u8)> {
+        let mut out = Vec::new();
+        for h in 0..self.height {
+            for w in 0..self.width {
+                let state = self.cells[h * self.width + w];
+                if state != 0 {
+                    out.push((w as i64, h as i64, state));
+                }
+            }
+        }
+        out
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.cells.len() + self.next.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::soup;
+    use crate::rule::Rule;
+    use crate::universe::Universe;
+
+    const WIREWORLD: &str = include_str!("../../rules/WireWorld.rule");
+
+    #[test]
+    fn electrons_run_along_a_wire() {
+        let rule: RuleFile = WIREWORLD.parse().unwrap();
+        assert_eq!(rule.name(), "WireWorld");
+        assert_eq!(rule.states(), 4);
+        assert_eq!(rule.color(3), [1.0, 128.0 / 255.0, 0.0, 1.0]);
+        let mut wire = RuleLoader::new(12, 3, rule.clone());
+        for x in 0..12 {
+            wire.set_state(x, 1, 3);
+        }
+        wire.set_state(0, 1, 2);
+        wire.set_state(1, 1, 1);
+        for t in 1..=5 {
+            wire.tick();
+            assert_eq!(wire.state(1 + t, 1), 1);
+            assert_eq!(wire.state(t, 1), 2);
+            assert_eq!(wire.state(t - 1, 1), 3);
+        }
+        assert_eq!(wire.population(), 12);
+
+        // Heads always become tails, and tails conductors, whatever is
+        // round them.
+        for (centre, next) in [(1, 2), (2, 3)] {
+            let mut crowd = RuleLoader::new(3, 3, rule.clone());
+            for y in 0..3 {
+                for x in 0..3 {
+                    crowd.set_state(x, y, 1);
+                }
+            }
+            crowd.set_state(1, 1, centre);
+            crowd.tick();
+            assert_eq!(crowd.state(1, 1), next);
+        }
+    }
+
+    #[test]
+    fn symmetries_and_bound_variables() {
+        let rule: RuleFile = "\
+@RULE Test
+@TABLE
+n_states:3
+neighborhood:vonNeumann
+symmetries:rotate4
+var a={1,2}
+0,1,2,0,0,1
+0,a,a,0,0,a
+"
+        .parse()
+        .unwrap();
+        // C,N,E,S,W,C' turned a quarter, or a half.
+        assert_eq!(rule.next(0, &[0, 1, 2, 0]), 1);
+        assert_eq!(rule.next(0, &[0, 0, 1, 2]), 1);
+        // A reflection is not a rotation.
+        assert_eq!(rule.next(0, &[2, 1, 0, 0]), 0);
+        // Both `a`s must be the same state, which the new state takes.
+        assert_eq!(rule.next(0, &[2, 2, 0, 0]), 2);
+        assert_eq!(rule.next(0, &[0, 0, 2, 2]), 2);
+        assert_eq!(rule.next(0, &[1, 0, 0, 2]), 0);
+    }
+
+    /// Writes out the `@TREE` of a two-state totalistic rule, as Golly's
+    /// RuleTreeGen would.
+    fn life_tree(rule: Rule) -> String {
+        fn node(depth: u8, count: u8, rule: Rule, lines: &mut Vec<String>) -> usize {
+            let line = if depth == 8 {
+                format!(
+                    "1 {} {}",
+                    rule.is_born(count) as u8,
+                    rule.survives(count) as u8
+                )
+            } else {
+                let dead = node(depth + 1, count, rule, lines);
+                let alive = node(depth + 1, count + 1, rule, lines);
+                format!("{} {} {}", 9 - depth, dead, alive)
+            };
+            match lines.iter().position(|l| *l == line) {
+                Some(i) => i,
+                None => {
+                    lines.push(line);
+                    lines.len() - 1
+                }
+            }
+        }
+        let mut lines = Vec::new();
+        node(0, 0, rule, &mut lines);
+        format!(
+            "@RULE Life\n@TREE\nnum_states=2\nnum_neighbors=8\nnum_nodes={}\n{}\n",
+            lines.len(),
+            lines.join("\n")
+        )
+    }
+
+    #[test]
+    fn tree_matches_dense_universe() {
+        let rule: Rule = "B36/S23".parse().unwrap();
+        let tree: RuleFile = life_tree(rule).parse().unwrap();
+        let (width, height) = (30, 20);
+        let mut dense = Universe::with_topology(width, height, Topology::Torus);
+        dense.set_rule(rule);
+        let mut loader = RuleLoader::with_topology(width, height, tree, Topology::Torus);
+        loader.set_threads(2);
+        soup(
+            3,
+            0..width as i64,
+            0..height as i64,
+            &mut [&mut dense, &mut loader],
+        );
+        for _ in 0..15 {
+            dense.tick();
+            loader.tick();
+        }
+        for y in 0..height as i64 {
+            for x in 0..width as i64 {
+                assert_eq!(dense.get(x, y), loader.get(x, y), "({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_broken_files() {
+        assert!("@TABLE\nn_states:2".parse::<RuleFile>().is_err());
+        assert!("@RULE X\n@TABLE\nn_states:2\n0,1,0"
+            .parse::<RuleFile>()
+            .is_err());
+        assert!("@RULE X\n@TABLE\nn_states:2\n0,1,1,1,0,0,0,0,0,7"
+            .parse::<RuleFile>()
+            .is_err());
+        assert!(
+            "@RULE X\n@TABLE\nn_states:2\nsymmetries:spin\n0,1,1,1,0,0,0,0,0,1"
+                .parse::<RuleFile>()
+                .is_err()
+        );
+        assert!("@RULE X\n@TREE\nnum_states=2\nnum_neighbors=4\n1 0 5"
+            .parse::<RuleFile>()
+            .is_err());
+        // A root at the wrong level, and a node whose children are states
+        // rather than nodes a level down.
+        let tree = life_tree(Rule::CONWAY);
+        assert!(tree
+            .replace("num_neighbors=8", "num_neighbors=4")
+            .parse::<RuleFile>()
+            .is_err());
+        assert!(tree
+            .replacen("\n2 ", "\n3 ", 1)
+            .parse::<RuleFile>()
+            .is_err());
+        assert!(
+            "@RULE X\n@TREE\nnum_states=2\nnum_neighbors=4\n1 0 1\n3 0 0"
+                .parse::<RuleFile>()
+                .is_err()
+        );
+    }
+}
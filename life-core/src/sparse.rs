@@ -0,0 +1,158 @@
+use std::collections::{HashMap, HashSet};
+use std::mem::size_of;
+
+use crate::engine::Engine;
+use crate::rule::Rule;
+
+/// An unbounded plane holding only its live cells, so patterns can travel
+/// as far as `i64` coordinates reach and the cost of a tick follows the
+/// population rather than the area.
+///
+/// Rules with B0 are not supported, since empty space must stay empty, and
+/// neither are Generations or non-totalistic rules.
+pub struct SparseLife {
+    alive: HashSet<(i64, i64)>,
+    /// Scratch space for neighbour counts, kept to reuse its allocation.
+    counts: HashMap<(i64, i64), u8>,
+    now: u64,
+    rule: Rule,
+}
+
+impl SparseLife {
+    pub fn new(rule: Rule) -> SparseLife {
+        assert!(!rule.is_born(0), "SparseLife cannot run B0 rules");
+        assert_eq!(rule.states(), 2, "SparseLife cannot run Generations rules");
+        assert!(
+            rule.is_totalistic(),
+            "SparseLife cannot run non-totalistic rules"
+        );
+        SparseLife {
+            alive: HashSet::new(),
+            counts: HashMap::new(),
+            now: 0,
+            rule,
+        }
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    /// The smallest rectangle `(min_x, min_y, max_x, max_y)` holding every
+    /// live cell, or `None` when everything is dead.
+    pub fn bounds(&self) -> Option<(i64, i64, i64, i64)> {
+        self.alive.iter().fold(None, |b, &(x, y)| match b {
+            None => Some((x, y, x, y)),
+            Some((x0, y0, x1, y1)) => Some((x0.min(x), y0.min(y), x1.max(x), y1.max(y))),
+        })
+    }
+
+    /// Iterates over the live cells in no particular order.
+    pub fn cells(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.alive.iter().copied()
+    }
+}
+
+impl Engine for SparseLife {
+    fn tick(&mut self) {
+        self.now += 1;
+        self.counts.clear();
+        let lattice = self.rule.lattice();
+        for &(x, y) in &self.alive {
+            for &[dx, dy] in lattice.offsets(x, y) {
+                *self
+                    .counts
+                    .entry((x + dx as i64, y + dy as i64))
+                    .or_insert(0) += 1;
+            }
+        }
+        let next: HashSet<(i64, i64)> = self
+            .counts
+            .iter()
+            .filter(|&(cell, &count)| self.rule.next(self.alive.contains(cell), count))
+            .map(|(&cell, _)| cell)
+            .collect();
+        // Live cells without a single live neighbour never made it into
+        // `counts`; S0 keeps them.
+        if self.rule.survives(0) {
+            let lonely: Vec<(i64, i64)> = self
+                .alive
+                .iter()
+                .filter(|cell| !self.counts.contains_key(cell))
+                .copied()
+                .collect();
+            self.alive = next;
+            self.alive.extend(lonely);
+        } else {
+            self.alive = next;
+        }
+    }
+
+    fn generation(&self) -> u64 {
+        self.now
+    }
+
+    fn set_generation(&mut self, generation: u64) {
+        self.now = generation;
+    }
+
+    fn population(&self) -> u64 {
+        self.alive.len() as u64
+    }
+
+    fn get(&self, x: i64, y: i64) -> bool {
+        self.alive.contains(&(x, y))
+    }
+
+    fn set(&mut self, x: i64, y: i64, alive: bool) {
+        if alive {
+            self.alive.insert((x, y));
+        } else {
+            self.alive.remove(&(x, y));
+        }
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64, u8)> {
+        self.alive.iter().map(|&(x, y)| (x, y, 1)).collect()
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.alive.capacity() * size_of::<(i64, i64)>()
+            + self.counts.capacity() * size_of::<((i64, i64), u8)>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashlife::HashLife;
+
+    #[test]
+    fn spaceship_leaves_the_neighbourhood() {
+        // The lightweight spaceship moves two cells west every four generations.
+        let lwss = [
+            (1, 0),
+            (4, 0),
+            (0, 1),
+            (0, 2),
+            (4, 2),
+            (0, 3),
+            (1, 3),
+            (2, 3),
+            (3, 3),
+        ];
+        let mut sparse = SparseLife::new(Rule::CONWAY);
+        let mut hash = HashLife::new(Rule::CONWAY);
+        for (x, y) in lwss {
+            sparse.set(x, y, true);
+            hash.set(x, y, true);
+        }
+        sparse.step(4000);
+        hash.step(4000);
+        assert_eq!(sparse.population(), 9);
+        assert_eq!(sparse.bounds(), Some((-2000, 0, -1996, 3)));
+        for (x, y) in sparse.cells() {
+            assert!(hash.get(x, y));
+        }
+    }
+}
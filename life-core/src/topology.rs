@@ -0,0 +1,220 @@
+use std::str::FromStr;
+
+use crate::rule::ParseRuleError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+}
+
+/// How one pair of opposite edges is glued together.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edge {
+    /// Not at all: everything beyond is permanently dead.
+    Dead,
+    /// Straight across, so what leaves one side re-enters on the other.
+    Wrap,
+    /// With a half twist, so what leaves one side re-enters on the other
+    /// mirrored along the edge.
+    Twist,
+}
+
+/// How the edges of a finite board are glued together.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Topology {
+    /// Everything beyond the edge is permanently dead.
+    #[default]
+    Plane,
+    /// Opposite edges are joined, so patterns leaving one side re-enter on the other.
+    Torus,
+    /// Only the edges crossed when moving along the axis are joined; the
+    /// other two are dead.
+    Cylinder(Axis),
+    /// Both pairs of edges are joined, the pair crossed when moving along the
+    /// axis with a twist.
+    KleinBottle(Axis),
+    /// Both pairs of edges are joined with a twist.
+    CrossSurface,
+}
+
+impl Topology {
+    /// How the left/right and the top/bottom edges are joined.
+    pub fn edges(&self) -> (Edge, Edge) {
+        match self {
+            Topology::Plane => (Edge::Dead, Edge::Dead),
+            Topology::Torus => (Edge::Wrap, Edge::Wrap),
+            Topology::Cylinder(Axis::X) => (Edge::Wrap, Edge::Dead),
+            Topology::Cylinder(Axis::Y) => (Edge::Dead, Edge::Wrap),
+            Topology::KleinBottle(Axis::X) => (Edge::Twist, Edge::Wrap),
+            Topology::KleinBottle(Axis::Y) => (Edge::Wrap, Edge::Twist),
+            Topology::CrossSurface => (Edge::Twist, Edge::Twist),
+        }
+    }
+
+    /// Maps a possibly out-of-range coordinate back onto a `width` x `height` board,
+    /// or returns `None` when it falls off the edge. Crossing a twisted edge
+    /// an even number of times mirrors nothing.
+    pub fn locate(&self, w: i64, h: i64, width: usize, height: usize) -> Option<(usize, usize)> {
+        let (width, height) = (width as i64, height as i64);
+        let (horizontal, vertical) = self.edges();
+        let (mut w, mut h) = (w, h);
+        if w < 0 || w >= width {
+            match horizontal {
+                Edge::Dead => return None,
+                Edge::Wrap => w = w.rem_euclid(width),
+                Edge::Twist => {
+                    if w.div_euclid(width) % 2 != 0 {
+                        h = height - 1 - h;
+                    }
+                    w = w.rem_euclid(width);
+                }
+            }
+        }
+        if h < 0 || h >= height {
+            match vertical {
+                Edge::Dead => return None,
+                Edge::Wrap => h = h.rem_euclid(height),
+                Edge::Twist => {
+                    if h.div_euclid(height) % 2 != 0 {
+                        w = width - 1 - w;
+                    }
+                    h = h.rem_euclid(height);
+                }
+            }
+        }
+        Some((w as usize, h as usize))
+    }
+}
+
+/// A bounded grid as given by a Golly rulestring suffix: `:P` plane, `:T`
+/// torus, `:K` Klein bottle and `:C` cross-surface, optionally followed by
+/// `width,height`.
+///
+/// As in Golly, a size of 0 leaves that axis unbounded, so `:T100,0` is a
+/// cylinder, and the Klein bottle's twisted pair of edges is marked with a
+/// `*`: `:K100*,80` twists the top and bottom edges, `:K100,80*` the left
+/// and right ones. Sizes left out, or unbounded, are up to the frontend.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Grid {
+    pub topology: Topology,
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+}
+
+impl FromStr for Grid {
+    type Err = ParseRuleError;
+
+    fn from_str(s: &str) -> Result<Grid, ParseRuleError> {
+        let spec = s.trim().trim_start_matches(':');
+        let mut chars = spec.chars();
+        let kind = chars.next().map(|c| c.to_ascii_uppercase());
+        let sizes = chars.as_str();
+
+        // Each size is digits with an optional trailing `*`.
+        let parse_size = |size: &str| -> Result<(Option<usize>, bool), ParseRuleError> {
+            let twisted = size.ends_with('*');
+            let digits = size.trim_end_matches('*');
+            match digits.parse::<usize>() {
+                Ok(0) => Ok((None, twisted)),
+                Ok(n) => Ok((Some(n), twisted)),
+                Err(_) => Err(ParseRuleError::new(s, "grid sizes must be numbers")),
+            }
+        };
+        let ((width, twist_w), (height, twist_h)) = match sizes.split_once(',') {
+            Some((w, h)) => (parse_size(w)?, parse_size(h)?),
+            None if sizes.is_empty() => ((None, false), (None, false)),
+            // A single size makes a square.
+            None => {
+                let size = parse_size(sizes)?;
+                (size, size)
+            }
+        };
+        if (twist_w || twist_h) && kind != Some('K') {
+            return Err(ParseRuleError::new(
+                s,
+                "only a Klein bottle has a twisted edge",
+            ));
+        }
+
+        let topology = match kind {
+            Some('P') => Topology::Plane,
+            Some('T') => match (width, height) {
+                _ if sizes.is_empty() => Topology::Torus,
+                (Some(_), None) => Topology::Cylinder(Axis::X),
+                (None, Some(_)) => Topology::Cylinder(Axis::Y),
+                (None, None) => Topology::Plane,
+                _ => Topology::Torus,
+            },
+            Some('K') => match (twist_w, twist_h) {
+                (false, true) => Topology::KleinBottle(Axis::X),
+                (true, true) => {
+                    return Err(ParseRuleError::new(
+                        s,
+                        "a Klein bottle twists one pair of edges",
+                    ))
+                }
+                _ => Topology::KleinBottle(Axis::Y),
+            },
+            Some('C') => Topology::CrossSurface,
+            _ => {
+                return Err(ParseRuleError::new(
+                    s,
+                    "unknown grid, expected P, T, K or C",
+                ))
+            }
+        };
+        Ok(Grid {
+            topology,
+            width,
+            height,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(s: &str) -> Grid {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_golly_suffixes() {
+        assert_eq!(
+            grid(":T100,80"),
+            Grid {
+                topology: Topology::Torus,
+                width: Some(100),
+                height: Some(80)
+            }
+        );
+        assert_eq!(grid(":T").topology, Topology::Torus);
+        assert_eq!(grid(":T0").topology, Topology::Plane);
+        assert_eq!(grid(":T100,0").topology, Topology::Cylinder(Axis::X));
+        assert_eq!(grid(":T0,50").topology, Topology::Cylinder(Axis::Y));
+        assert_eq!(grid(":K40*,30").topology, Topology::KleinBottle(Axis::Y));
+        assert_eq!(grid(":K40,30*").topology, Topology::KleinBottle(Axis::X));
+        assert_eq!(grid(":C20").topology, Topology::CrossSurface);
+        assert_eq!(grid(":P30,20").width, Some(30));
+        assert!(":Q10,10".parse::<Grid>().is_err());
+        assert!(":T10*,10".parse::<Grid>().is_err());
+    }
+
+    #[test]
+    fn twisted_edges_mirror() {
+        let klein = Topology::KleinBottle(Axis::X);
+        assert_eq!(klein.locate(-1, 0, 10, 6), Some((9, 5)));
+        assert_eq!(klein.locate(3, -1, 10, 6), Some((3, 5)));
+        let cross = Topology::CrossSurface;
+        assert_eq!(cross.locate(3, 6, 10, 6), Some((6, 0)));
+        assert_eq!(cross.locate(10, 1, 10, 6), Some((0, 4)));
+        assert_eq!(Topology::Cylinder(Axis::X).locate(4, -1, 10, 6), None);
+        // Two periods away the two twists cancel out.
+        assert_eq!(klein.locate(2 * 10 + 3, 1, 10, 6), Some((3, 1)));
+        assert_eq!(klein.locate(-2 * 10 + 3, 1, 10, 6), Some((3, 1)));
+        assert_eq!(cross.locate(2 * 10 + 3, 2 * 6 + 1, 10, 6), Some((3, 1)));
+        assert_eq!(cross.locate(13, 7, 10, 6), Some((6, 4)));
+    }
+}
@@ -0,0 +1,685 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::board::{Board, Cell, Live, BLACK};
+use crate::engine::Engine;
+use crate::noise::Region;
+use crate::parallel::for_each_band;
+use crate::random::Rng;
+use crate::rule::{ParseRuleError, Rule};
+use crate::topology::Topology;
+
+/// The order the cells of a `Universe` are updated in during a tick.
+///
+/// Written `sync`, `sweep`, `random` or `alpha=0.5`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Update {
+    /// Every cell at once, from the generation before: the usual way.
+    #[default]
+    Synchronous,
+    /// One cell at a time, row by row, each seeing the cells updated before
+    /// it.
+    Sweep,
+    /// One cell at a time in a fresh random order every tick, each seeing
+    /// the cells updated before it.
+    RandomOrder,
+    /// Every cell at once, but each only with probability `alpha`; the others
+    /// stay as they were.
+    Alpha(f64),
+}
+
+impl fmt::Display for Update {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Update::Synchronous => write!(f, "sync"),
+            Update::Sweep => write!(f, "sweep"),
+            Update::RandomOrder => write!(f, "random"),
+            Update::Alpha(alpha) => write!(f, "alpha={}", alpha),
+        }
+    }
+}
+
+impl FromStr for Update {
+    type Err = ParseRuleError;
+
+    fn from_str(s: &str) -> Result<Update, ParseRuleError> {
+        let update = s.trim().to_ascii_lowercase();
+        match update.as_str() {
+            "sync" | "synchronous" => Ok(Update::Synchronous),
+            "sweep" => Ok(Update::Sweep),
+            "random" => Ok(Update::RandomOrder),
+            _ => update
+                .strip_prefix("alpha=")
+                .and_then(|alpha| alpha.parse().ok())
+                .filter(|alpha| (0.0..=1.0).contains(alpha))
+                .map(Update::Alpha)
+                .ok_or_else(|| ParseRuleError::new(s, "expected sync, sweep, random or alpha=0.5")),
+        }
+    }
+}
+
+/// A finite Life-like cellular automaton, B3/S23 unless told otherwise.
+///
+/// Two boards are kept: the current generation and the one before it. `tick`
+/// writes the next generation over the older board and swaps them.
+///
+/// The board is cut into tiles of `TILE` x `TILE` cells, and a synchronous
+/// tick only recomputes the tiles next to one that changed in the tick
+/// before, or that was edited since. Everywhere else the older board already
+/// holds the same cells, so a soup that has settled into ash costs little
+/// more than the parts of it still moving.
+pub struct Universe {
+    twin: Vec<Board>,
+    iboard: usize,
+    now: u64,
+    width: usize,
+    height: usize,
+    rule: Rule,
+    threads: usize,
+    update: Update,
+    /// Picks the cells and orders of the asynchronous updates.
+    rng: Rng,
+    /// Whether each tile, row by row, is recomputed next tick.
+    active: Vec<bool>,
+}
+
+/// The side of the square tiles a `Universe` tracks activity in.
+pub const TILE: usize = 16;
+
+impl Universe {
+    /// Creates an empty universe whose edges are dead.
+    pub fn new(width: usize, height: usize) -> Universe {
+        Universe::with_topology(width, height, Topology::Plane)
+    }
+
+    pub fn with_topology(width: usize, height: usize, topology: Topology) -> Universe {
+        Universe {
+            twin: vec![
+                Board::with_topology(width, height, topology),
+                Board::with_topology(width, height, topology),
+            ],
+            iboard: 0,
+            now: 0,
+            width,
+            height,
+            rule: Rule::CONWAY,
+            threads: 1,
+            update: Update::Synchronous,
+            rng: Rng::new(0),
+            active: vec![true; width.div_ceil(TILE) * height.div_ceil(TILE)],
+        }
+    }
+
+    /// Fills the current generation, asking `alive` once per cell, row by row.
+    pub fn randomize(&mut self, mut alive: impl FnMut() -> bool) {
+        let now = self.now;
+        let board = self.get_now_board_mut();
+        for h in 0..board.height() {
+            for w in 0..board.width() {
+                let live = if alive() { Live::Alive } else { Live::Dead };
+                board.set(
+                    w,
+                    h,
+                    Cell {
+                        live,
+                        color: BLACK,
+                        birth_day: now,
+                        family: 1,
+                    },
+                )
+            }
+        }
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+        self.active.fill(true);
+    }
+
+    /// Splits each tick into bands of rows stepped on `threads` threads. The
+    /// result is the same for any number of threads. Only synchronous and
+    /// alpha-asynchronous updates are split.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    pub fn update(&self) -> Update {
+        self.update
+    }
+
+    /// Switches to `update`, drawing the random choices it makes from `seed`
+    /// so a run can be replayed.
+    pub fn set_update(&mut self, update: Update, seed: u64) {
+        self.update = update;
+        self.rng = Rng::new(seed);
+        self.active.fill(true);
+    }
+
+    /// Number of tiles across and down.
+    fn tiles(&self) -> (usize, usize) {
+        (self.width.div_ceil(TILE), self.height.div_ceil(TILE))
+    }
+
+    /// Whether the tile `(tx, ty)` is recomputed next tick.
+    pub fn is_tile_active(&self, tx: usize, ty: usize) -> bool {
+        self.active[ty * self.tiles().0 + tx]
+    }
+
+    /// Number of tiles recomputed next tick.
+    pub fn active_tile_count(&self) -> usize {
+        self.active.iter().filter(|&&active| active).count()
+    }
+
+    /// Marks the tiles touching any of `changed`, given per tile, to be
+    /// recomputed next tick, and no others.
+    fn wake(&mut self, changed: &[bool]) {
+        self.active.fill(false);
+        let (tiles_x, _) = self.tiles();
+        for t in (0..changed.len()).filter(|&t| changed[t]) {
+            self.wake_around(t % tiles_x, t / tiles_x);
+        }
+    }
+
+    /// Marks the tile `(tx, ty)` and the tiles round it to be recomputed next
+    /// tick. Where edges are joined, a tile along an edge wakes every tile
+    /// along the edges, which is simpler than following each twist.
+    fn wake_around(&mut self, tx: usize, ty: usize) {
+        let (tiles_x, tiles_y) = self.tiles();
+        for y in ty.saturating_sub(1)..(ty + 2).min(tiles_y) {
+            for x in tx.saturating_sub(1)..(tx + 2).min(tiles_x) {
+                self.active[y * tiles_x + x] = true;
+            }
+        }
+        let on_edge = |x: usize, y: usize| x == 0 || y == 0 || x + 1 == tiles_x || y + 1 == tiles_y;
+        if on_edge(tx, ty) && self.twin[self.iboard].topology() != Topology::Plane {
+            for y in 0..tiles_y {
+                for x in (0..tiles_x).filter(|&x| on_edge(x, y)) {
+                    self.active[y * tiles_x + x] = true;
+                }
+            }
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Number of ticks since the universe was created.
+    pub fn generation(&self) -> u64 {
+        self.now
+    }
+
+    pub fn get_now_board(&self) -> &Board {
+        &self.twin[self.iboard]
+    }
+
+    /// The current generation, to edit. Every tile is recomputed next tick,
+    /// as the edits cannot be followed.
+    pub fn get_now_board_mut(&mut self) -> &mut Board {
+        self.active.fill(true);
+        &mut self.twin[self.iboard]
+    }
+
+    /// The generation before the current one.
+    pub fn get_pre_board(&self) -> &Board {
+        &self.twin[(self.iboard + 1) % 2]
+    }
+
+    pub fn is_alive(&self, w: usize, h: usize) -> bool {
+        self.get_now_board().is_alive(w, h)
+    }
+
+    /// Makes `(w, h)` alive or dead in the current generation. Cells born
+    /// this way have the first colour.
+    pub fn set_alive(&mut self, w: usize, h: usize, alive: bool) {
+        self.wake_around(w / TILE, h / TILE);
+        let now = self.now;
+        let cell = self.twin[self.iboard].get_mut(w, h);
+        match (cell.live, alive) {
+            (Live::Alive, true) => {}
+            (_, true) => {
+                cell.live = Live::Alive;
+                cell.birth_day = now;
+                cell.family = 1;
+            }
+            (_, false) => cell.live = Live::Dead,
+        }
+    }
+
+    /// The colour of a live cell under a colour rule, from 1, and 0 for
+    /// cells that are not alive.
+    pub fn family(&self, w: usize, h: usize) -> u8 {
+        let cell = self.get_now_board().get(w, h);
+        match cell.live {
+            Live::Alive => cell.family,
+            _ => 0,
+        }
+    }
+
+    /// Makes `(w, h)` alive in colour `family`, keeping its birth day if it
+    /// was alive already.
+    pub fn set_family(&mut self, w: usize, h: usize, family: u8) {
+        self.set_alive(w, h, true);
+        self.twin[self.iboard].get_mut(w, h).family = family;
+    }
+
+    /// The Golly state number of `(w, h)`.
+    fn cell_state(&self, w: usize, h: usize) -> u8 {
+        let cell = self.get_now_board().get(w, h);
+        match cell.live {
+            Live::Alive if self.rule.colors() > 1 => cell.family,
+            live => live.state(),
+        }
+    }
+
+    /// Advances one generation under the universe's rule, in the order its
+    /// `Update` gives.
+    ///
+    /// Survivors keep their colour and birth day; newborns start out black with
+    /// `birth_day` set to the new generation. Under a Generations rule, dying
+    /// cells keep them too until they are dead. Under a colour rule newborns
+    /// take their `family` from their parents.
+    pub fn tick(&mut self) {
+        let prev_i = self.iboard;
+        let now_i = (prev_i + 1) % 2;
+        self.now += 1;
+
+        let (a, b) = self.twin.split_at_mut(1);
+        let (prev, now) = if prev_i == 0 {
+            (&a[0], &mut b[0])
+        } else {
+            (&b[0], &mut a[0])
+        };
+        let (rule, generation) = (self.rule, self.now);
+        let (tiles_x, tiles_y) = (self.width.div_ceil(TILE), self.height.div_ceil(TILE));
+        let width = self.width;
+        if self.update != Update::Synchronous {
+            self.active.fill(true);
+        }
+        let active = &self.active;
+        match self.update {
+            Update::Synchronous | Update::Alpha(_) => {
+                for_each_band(now.rows_mut(), 1, self.threads, |first, rows| {
+                    for (h, row) in (first..).zip(rows) {
+                        for tx in (0..tiles_x).filter(|tx| active[h / TILE * tiles_x + tx]) {
+                            let tile = tx * TILE..(tx * TILE + TILE).min(width);
+                            for (w, cell) in tile.clone().zip(&mut row[tile]) {
+                                *cell = next_cell(rule, prev, w, h, generation);
+                            }
+                        }
+                    }
+                });
+                if let Update::Alpha(alpha) = self.update {
+                    for h in 0..self.height {
+                        for w in 0..self.width {
+                            if !self.rng.gen_bool(alpha) {
+                                now.set(w, h, *prev.get(w, h));
+                            }
+                        }
+                    }
+                }
+            }
+            Update::Sweep | Update::RandomOrder => {
+                now.clone_from(prev);
+                let mut order: Vec<usize> = (0..self.width * self.height).collect();
+                if let Update::RandomOrder = self.update {
+                    for i in (1..order.len()).rev() {
+                        order.swap(i, self.rng.below(i as u64 + 1) as usize);
+                    }
+                }
+                for i in order {
+                    let (w, h) = (i % self.width, i / self.width);
+                    let cell = next_cell(rule, now, w, h, generation);
+                    now.set(w, h, cell);
+                }
+            }
+        }
+
+        // The tiles that changed wake themselves and their neighbours.
+        let mut changed = vec![false; tiles_x * tiles_y];
+        for (t, changed) in changed.iter_mut().enumerate() {
+            let (tx, ty) = (t % tiles_x, t / tiles_x);
+            *changed = self.active[t]
+                && (ty * TILE..(ty * TILE + TILE).min(self.height)).any(|h| {
+                    (tx * TILE..(tx * TILE + TILE).min(self.width)).any(|w| {
+                        let (old, new) = (prev.get(w, h), now.get(w, h));
+                        old.live != new.live || old.family != new.family
+                    })
+                });
+        }
+        self.iboard = now_i;
+        self.wake(&changed);
+    }
+}
+
+/// The cell `(w, h)` of `board` after one generation under `rule`, born in
+/// `generation` if it comes alive.
+fn next_cell(rule: Rule, board: &Board, w: usize, h: usize, generation: u64) -> Cell {
+    let old = board.get(w, h);
+    let next = if rule.is_totalistic() {
+        let count = board.alive_neighbors_count_on(rule.lattice(), w, h);
+        rule.next_live(old.live, count)
+    } else {
+        rule.next_live_around(old.live, board.neighborhood(w, h))
+    };
+    match (old.live, next) {
+        (Live::Alive, Live::Alive) => *old,
+        (_, Live::Alive) => Cell {
+            live: Live::Alive,
+            color: BLACK,
+            birth_day: generation,
+            family: match rule.colors() {
+                1 => 1,
+                _ => rule.inherit(board.parents_on(rule.lattice(), w, h)),
+            },
+        },
+        _ => Cell { live: next, ..*old },
+    }
+}
+
+impl Engine for Universe {
+    fn tick(&mut self) {
+        Universe::tick(self)
+    }
+
+    fn generation(&self) -> u64 {
+        self.now
+    }
+
+    fn set_generation(&mut self, generation: u64) {
+        self.now = generation;
+    }
+
+    fn population(&self) -> u64 {
+        self.get_now_board().cell_statics().0 as u64
+    }
+
+    fn get(&self, x: i64, y: i64) -> bool {
+        self.get_now_board()
+            .topology()
+            .locate(x, y, self.width, self.height)
+            .is_some_and(|(w, h)| self.is_alive(w, h))
+    }
+
+    /// Under a colour rule, live cells are in the state of their colour.
+    fn state(&self, x: i64, y: i64) -> u8 {
+        self.get_now_board()
+            .topology()
+            .locate(x, y, self.width, self.height)
+            .map_or(0, |(w, h)| self.cell_state(w, h))
+    }
+
+    fn set(&mut self, x: i64, y: i64, alive: bool) {
+        if let Some((w, h)) = self
+            .get_now_board()
+            .topology()
+            .locate(x, y, self.width, self.height)
+        {
+            self.set_alive(w, h, alive)
+        }
+    }
+
+    fn set_state(&mut self, x: i64, y: i64, state: u8) {
+        if state <= 1 {
+            return self.set(x, y, state == 1);
+        }
+        if let Some((w, h)) = self
+            .get_now_board()
+            .topology()
+            .locate(x, y, self.width, self.height)
+        {
+            if self.rule.colors() > 1 {
+                self.set_family(w, h, state);
+            } else {
+                self.get_now_board_mut().get_mut(w, h).live = Live::Dying(state - 1);
+            }
+        }
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64, u8)> {
+        let mut out = Vec::new();
+        for h in 0..self.height {
+            for w in 0..self.width {
+                let state = self.cell_state(w, h);
+                if state != 0 {
+                    out.push((w as i64, h as i64, state));
+                }
+            }
+        }
+        out
+    }
+
+    fn memory_usage(&self) -> usize {
+        2 * self.width * self.height * std::mem::size_of::<Cell>() + self.active.len()
+    }
+
+    fn active_tiles(&self) -> Option<Vec<Region>> {
+        let (tiles_x, _) = self.tiles();
+        let tiles = (0..self.active.len())
+            .filter(|&t| self.active[t])
+            .map(|t| Region {
+                x: (t % tiles_x * TILE) as i64,
+                y: (t / tiles_x * TILE) as i64,
+                width: TILE.min(self.width - t % tiles_x * TILE),
+                height: TILE.min(self.height - t / tiles_x * TILE),
+            })
+            .collect();
+        Some(tiles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::soup;
+
+    fn alive_cells(u: &Universe) -> Vec<(usize, usize)> {
+        let mut cells = vec![];
+        for h in 0..u.height() {
+            for w in 0..u.width() {
+                if u.is_alive(w, h) {
+                    cells.push((w, h));
+                }
+            }
+        }
+        cells
+    }
+
+    #[test]
+    fn blinker_oscillates() {
+        let mut u = Universe::new(5, 5);
+        for w in 1..4 {
+            u.set_alive(w, 2, true);
+        }
+        u.tick();
+        assert_eq!(alive_cells(&u), vec![(2, 1), (2, 2), (2, 3)]);
+        u.tick();
+        assert_eq!(alive_cells(&u), vec![(1, 2), (2, 2), (3, 2)]);
+        assert_eq!(u.generation(), 2);
+    }
+
+    #[test]
+    fn seeds_has_no_survivors() {
+        let mut u = Universe::new(4, 4);
+        u.set_rule("B2/S".parse().unwrap());
+        u.set_alive(1, 1, true);
+        u.set_alive(2, 1, true);
+        u.tick();
+        assert_eq!(alive_cells(&u), vec![(1, 0), (2, 0), (1, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn brians_brain_cells_fade_before_dying() {
+        let mut u = Universe::new(4, 4);
+        u.set_rule("/2/3".parse().unwrap());
+        u.set_alive(1, 1, true);
+        u.set_alive(2, 1, true);
+        u.tick();
+        assert_eq!(alive_cells(&u), vec![(1, 0), (2, 0), (1, 2), (2, 2)]);
+        assert_eq!(u.state(1, 1), 2);
+        assert_eq!(u.state(2, 1), 2);
+        u.tick();
+        assert_eq!(u.state(1, 1), 0);
+        // Dying cells do not count, so the newborns only have each other.
+        assert_eq!(u.state(1, 0), 2);
+    }
+
+    #[test]
+    fn hexagonal_rule_counts_six_neighbours() {
+        let mut u = Universe::new(5, 5);
+        u.set_rule("B1/SH".parse().unwrap());
+        u.set_alive(2, 2, true);
+        u.tick();
+        // Row 2 is even, so the rows above and below lean left.
+        assert_eq!(
+            alive_cells(&u),
+            vec![(1, 1), (2, 1), (1, 2), (3, 2), (1, 3), (2, 3)]
+        );
+    }
+
+    #[test]
+    fn non_totalistic_rule_looks_at_arrangements() {
+        for (rule, born) in [("B2a/S", 4), ("B2-a/S", 0)] {
+            let mut u = Universe::new(4, 4);
+            u.set_rule(rule.parse().unwrap());
+            u.set_alive(1, 1, true);
+            u.set_alive(2, 1, true);
+            u.tick();
+            assert_eq!(alive_cells(&u).len(), born, "{}", rule);
+        }
+    }
+
+    #[test]
+    fn newborns_take_their_parents_colour() {
+        let mut u = Universe::new(5, 5);
+        u.set_rule(Rule::QUADLIFE);
+        // A blinker of colours 2, 2 and 3 is born again in colour 2 at its
+        // ends, the middle keeping its own colour.
+        u.set_state(1, 2, 2);
+        u.set_state(2, 2, 2);
+        u.set_state(3, 2, 3);
+        u.tick();
+        assert_eq!(u.live_cells(), vec![(2, 1, 2), (2, 2, 2), (2, 3, 2)]);
+        // Three parents in three colours give a child in the fourth.
+        let mut u = Universe::new(5, 5);
+        u.set_rule(Rule::QUADLIFE);
+        u.set_state(1, 2, 1);
+        u.set_state(2, 2, 2);
+        u.set_state(3, 2, 3);
+        u.tick();
+        assert_eq!(u.state(2, 1), 4);
+        assert_eq!(u.population(), 3);
+    }
+
+    #[test]
+    fn asynchronous_updates() {
+        let blinker = |update, seed| {
+            let mut u = Universe::new(5, 5);
+            u.set_update(update, seed);
+            for w in 1..4 {
+                u.set_alive(w, 2, true);
+            }
+            u.tick();
+            alive_cells(&u)
+        };
+        let vertical = vec![(2, 1), (2, 2), (2, 3)];
+        assert_eq!(blinker(Update::Synchronous, 0), vertical);
+        assert_eq!(blinker(Update::Alpha(1.0), 0), vertical);
+        assert_eq!(blinker(Update::Alpha(0.0), 0), vec![(1, 2), (2, 2), (3, 2)]);
+        // Row by row, (2, 1) is born first and gives (3, 1) its third
+        // neighbour, and the middle then dies of crowding before (3, 2) is
+        // reached.
+        assert_eq!(
+            blinker(Update::Sweep, 0),
+            vec![(2, 1), (3, 1), (1, 2), (3, 2)]
+        );
+        assert_eq!(
+            blinker(Update::RandomOrder, 7),
+            blinker(Update::RandomOrder, 7)
+        );
+        assert!((0..20).any(|seed| blinker(Update::RandomOrder, seed) != vertical));
+
+        assert_eq!("alpha=0.25".parse(), Ok(Update::Alpha(0.25)));
+        assert_eq!("Random".parse(), Ok(Update::RandomOrder));
+        assert_eq!(Update::Sweep.to_string().parse(), Ok(Update::Sweep));
+        assert!("alpha=2".parse::<Update>().is_err());
+    }
+
+    #[test]
+    fn skipping_quiet_tiles_changes_nothing() {
+        for (topology, rule) in [
+            (Topology::Plane, "B3/S23"),
+            (Topology::Torus, "B3/S23"),
+            (Topology::KleinBottle(crate::topology::Axis::X), "/2/3"),
+        ] {
+            let mut tracked = Universe::with_topology(70, 50, topology);
+            let mut full = Universe::with_topology(70, 50, topology);
+            for u in [&mut tracked, &mut full] {
+                u.set_rule(rule.parse().unwrap());
+                soup(7, 10..40, 10..30, &mut [u]);
+            }
+            for _ in 0..300 {
+                tracked.tick();
+                // Taking the board to edit wakes every tile.
+                full.get_now_board_mut();
+                full.tick();
+                assert_eq!(tracked.live_cells(), full.live_cells(), "{}", rule);
+            }
+            if topology == Topology::Plane {
+                assert!(tracked.active_tile_count() < tracked.active.len());
+            }
+        }
+    }
+
+    #[test]
+    fn still_lifes_leave_every_tile_quiet() {
+        let mut u = Universe::new(64, 64);
+        for (w, h) in [(20, 20), (21, 20), (20, 21), (21, 21)] {
+            u.set_alive(w, h, true);
+        }
+        u.step(2);
+        assert_eq!(u.active_tile_count(), 0);
+        assert_eq!(u.active_tiles(), Some(vec![]));
+        // An edit wakes the tiles round it, and a lone cell dies in one tick.
+        u.set_alive(40, 40, true);
+        assert_eq!(u.active_tile_count(), 9);
+        u.step(2);
+        assert_eq!(u.active_tile_count(), 0);
+        assert_eq!(u.population(), 4);
+    }
+
+    #[test]
+    fn threads_do_not_change_the_result() {
+        let mut serial = Universe::with_topology(50, 41, Topology::Torus);
+        let mut parallel = Universe::with_topology(50, 41, Topology::Torus);
+        parallel.set_threads(4);
+        soup(99, 0..50, 0..41, &mut [&mut serial, &mut parallel]);
+        for _ in 0..20 {
+            serial.tick();
+            parallel.tick();
+        }
+        assert_eq!(alive_cells(&serial), alive_cells(&parallel));
+    }
+
+    #[test]
+    fn glider_wraps_on_torus() {
+        let mut u = Universe::with_topology(6, 6, Topology::Torus);
+        for (w, h) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            u.set_alive(w, h, true);
+        }
+        let start = alive_cells(&u);
+        // A glider moves one cell diagonally every four generations.
+        for _ in 0..4 * 6 {
+            u.tick();
+        }
+        assert_eq!(alive_cells(&u), start);
+    }
+}
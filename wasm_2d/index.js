@@ -1,4 +1,4 @@
-import wasmInit, {Universe} from "./pkg/wasm.js";
+import wasmInit, {Universe, EngineUniverse, is_block_rule, is_continuous_rule} from "./pkg/wasm.js";
 
 const runWasm = async () => {
     // Instantiate our wasm module
@@ -31,7 +31,54 @@ const runWasm = async () => {
 
     const checkerBoardSize = rustWasm.get_checkerboard_size();
     const tickTimeOut = rustWasm.tick_timeout();
-    let universeNew = new Universe(checkerBoardSize,checkerBoardSize);
+    // A rule in the page's query string, e.g. ?rule=B2/S34H, picks the lattice,
+    // ?rule=Immigration or ?rule=QuadLife colours cells by their parents;
+    // a block rule such as ?rule=Critters runs on the Margolus neighbourhood
+    // and ?rule=Lenia or ?rule=SmoothLife on cells valued from 0 to 1
+    const params = new URLSearchParams(window.location.search);
+    const rule = params.get("rule");
+    // ?rulefile=WireWorld.rule loads a Golly rule file from the server instead
+    const ruleFile = params.get("rulefile");
+    // ?seed=123 replays a soup; the seed of every run is logged to the console
+    const seed = params.has("seed") ? BigInt(params.get("seed")) : undefined;
+    let universeNew = ruleFile
+        ? EngineUniverse.with_rule_file(await (await fetch(ruleFile)).text(), seed)
+        : rule && is_block_rule(rule)
+        ? EngineUniverse.with_block_rule(rule, seed)
+        : rule && is_continuous_rule(rule)
+        ? EngineUniverse.with_continuous_rule(rule, seed)
+        : rule
+        ? Universe.with_rule(rule, seed)
+        : new Universe(checkerBoardSize,checkerBoardSize, seed);
+    // ?noise=B0.001/D0.001 replaces the random births and deaths after every
+    // tick, e.g. ?noise= for none or ?noise=B0.01/@0,0,20x20 for a corner only
+    if (params.has("noise")) {
+        universeNew.set_noise(params.get("noise"));
+    }
+    // ?update=random or ?update=alpha=0.5 updates the cells of a plain rule
+    // asynchronously
+    if (params.has("update") && universeNew instanceof Universe) {
+        universeNew.set_update(params.get("update"));
+    }
+
+    // Space pauses, "," and "." step back and forward through the history,
+    // Home and End jump to its ends. Running on from the past branches off.
+    let paused = false;
+    document.addEventListener("keydown", (event) => {
+        const moves = {
+            ",": () => universeNew.step_back(),
+            ".": () => universeNew.step_forward(),
+            Home: () => universeNew.scrub(universeNew.history_first()),
+            End: () => universeNew.scrub(universeNew.history_last()),
+        };
+        if (event.key === " ") {
+            paused = !paused;
+        } else if (moves[event.key]) {
+            paused = true;
+            moves[event.key]();
+            showBuffer();
+        }
+    });
 
     const drawCheckerBoard = () => {
 
@@ -44,8 +91,15 @@ const runWasm = async () => {
         //     getLightValue(),
         //     getLightValue()
         // );
+        if (paused) {
+            return;
+        }
         universeNew.tick();
+        showBuffer();
+    };
 
+    // Copies the frame the universe drew in wasm memory onto the canvas.
+    const showBuffer = () => {
         // Create a Uint8Array to give us access to Wasm Memory
         const wasmByteMemoryArray = new Uint8Array(rustWasm.memory.buffer);
 
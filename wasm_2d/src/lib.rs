@@ -1,15 +1,16 @@
 // The wasm-pack uses wasm-bindgen to build and generate JavaScript binding file.
 // Import the wasm-bindgen crate.
-
-use getrandom::getrandom;
-// The wasm-pack uses wasm-bindgen to build and generate JavaScript binding file.
-// Import the wasm-bindgen crate.
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 
 // Define the size of our "checkerboard"
 pub const CHECKERBOARD_SIZE: usize = 100;
 pub const TICKTIMEOUT: usize = 10;
+// Canvas pixels per row of cells on the hexagonal and triangular lattices
+pub const CELL_PIXELS: usize = 4;
+// Generations kept to step back to, and how often a full snapshot is taken
+pub const HISTORY_LENGTH: usize = 1000;
+pub const HISTORY_SNAPSHOT_EVERY: usize = 32;
 
 #[wasm_bindgen]
 pub fn get_checkerboard_size() -> usize {
@@ -52,33 +53,28 @@ static mut OUTPUT_BUFFER: [u8; OUTPUT_BUFFER_SIZE] = [0; OUTPUT_BUFFER_SIZE];
 // in wasm memory
 #[wasm_bindgen]
 pub fn get_output_buffer_pointer() -> *const u8 {
-    let pointer: *const u8;
-    unsafe {
-        pointer = OUTPUT_BUFFER.as_ptr();
-    }
-
-    return pointer;
+    std::ptr::addr_of!(OUTPUT_BUFFER) as *const u8
 }
 
 // Function to generate our checkerboard, pixel by pixel
 #[wasm_bindgen]
 pub fn generate_checker_board(
-    mut dark_value_red: u8,
-    mut dark_value_green: u8,
-    mut dark_value_blue: u8,
-    mut light_value_red: u8,
-    mut light_value_green: u8,
-    mut light_value_blue: u8,
+    _dark_value_red: u8,
+    _dark_value_green: u8,
+    _dark_value_blue: u8,
+    _light_value_red: u8,
+    _light_value_green: u8,
+    _light_value_blue: u8,
 ) {
     let mut buf = [0u8; 32];
-    getrandom::getrandom(&mut buf);
-
-    dark_value_red = buf[0];
-    dark_value_green = buf[1];
-    dark_value_blue = buf[2];
-    light_value_red = buf[3];
-    light_value_green = buf[4];
-    light_value_blue = buf[5];
+    getrandom::getrandom(&mut buf).unwrap();
+
+    let dark_value_red = buf[0];
+    let dark_value_green = buf[1];
+    let dark_value_blue = buf[2];
+    let light_value_red = buf[3];
+    let light_value_green = buf[4];
+    let light_value_blue = buf[5];
     // Since Linear memory is a 1 dimensional array, but we want a grid
     // we will be doing 2d to 1d mapping
     // https://softwareengineering.stackexchange.com/questions/212808/treating-a-1d-data-structure-as-2d-grid
@@ -116,7 +112,7 @@ pub fn generate_checker_board(
 
             // Finally store the values.
             unsafe {
-                OUTPUT_BUFFER[square_rgba_index + 0] = square_value_red; // Red
+                OUTPUT_BUFFER[square_rgba_index] = square_value_red; // Red
                 OUTPUT_BUFFER[square_rgba_index + 1] = square_value_green; // Green
                 OUTPUT_BUFFER[square_rgba_index + 2] = square_value_blue; // Blue
                 OUTPUT_BUFFER[square_rgba_index + 3] = 255; // Alpha (Always Opaque)
@@ -126,210 +122,449 @@ pub fn generate_checker_board(
 }
 
 #[wasm_bindgen]
-#[derive(PartialEq)]
-pub enum Live {
-    Alive,
-    Dead,
+extern "C" {
+    // Use `js_namespace` here to bind `console.log(..)` instead of just
+    // `log(..)`
+    #[wasm_bindgen(js_namespace = console)]
+    fn log(s: &str);
+
+    // The `console.log` is quite polymorphic, so we can bind it with multiple
+    // signatures. Note that we need to use `js_name` to ensure we always call
+    // `log` in JS.
+    #[wasm_bindgen(js_namespace = console, js_name = log)]
+    fn log_u32(a: u32);
+
+    // Multiple arguments too!
+    #[wasm_bindgen(js_namespace = console, js_name = log)]
+    fn log_many(a: &str, b: &str);
 }
 
 #[wasm_bindgen]
-pub struct Cell {
-    live: Live,
-    birth_day: u64,
+pub struct Universe {
+    inner: life_core::Universe,
+    // Canvas pixels per row of cells
+    scale: f64,
+    rng: life_core::Rng,
+    // Random births and deaths after every tick
+    noise: life_core::Noise,
+    history: life_core::History,
 }
 
-#[wasm_bindgen]
-pub struct Board {
-    board: Vec<Vec<Cell>>,
-    width: usize,
-    height: usize,
+// Noise for a universe the page gives none: a dead cell comes alive about
+// once in ten thousand ticks, so a board that has settled keeps stirring.
+const DEFAULT_NOISE: &str = "B0.0001";
+
+// Parses noise such as `B0.001/D2=0.5/@0,0,50x50`, see `life_core::Noise`.
+fn parse_noise(noise: &str) -> Result<life_core::Noise, JsValue> {
+    noise
+        .parse()
+        .map_err(|err: life_core::ParseRuleError| JsValue::from_str(&err.to_string()))
 }
 
-pub const DIRECTIONS: [[i32; 2]; 8] = [
-    [-1, -1],
-    [-1, 0],
-    [-1, 1],
-    [0, -1],
-    [0, 1],
-    [1, -1],
-    [1, 0],
-    [1, 1],
-];
+// The generator for a universe: from `seed` when the page gives one, and
+// otherwise from the browser's randomness. The seed goes to the console so
+// the run can be replayed, here or natively with `--seed`.
+fn seeded_rng(seed: Option<u64>) -> life_core::Rng {
+    let seed = seed.unwrap_or_else(|| {
+        let mut buf = [0u8; 8];
+        getrandom::getrandom(&mut buf).unwrap();
+        u64::from_le_bytes(buf)
+    });
+    log(&format!("seed {}", seed));
+    life_core::Rng::new(seed)
+}
 
 #[wasm_bindgen]
-impl Board {
-    pub fn new(width: usize, height: usize) -> Board {
-        let mut board: Vec<Vec<Cell>> = Vec::new();
-        for _ in 0..height {
-            let mut line: Vec<Cell> = Vec::new();
-            for w in 0..width {
-                line.push(Cell {
-                    live: Live::Alive,
-                    birth_day: 0,
-                })
+impl Universe {
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: usize, height: usize, seed: Option<u64>) -> Universe {
+        let mut rng = seeded_rng(seed);
+        let mut inner = life_core::Universe::new(width, height);
+        inner.randomize(|| rng.gen_bool(1.0 / 2.0));
+        Universe::from_parts(inner, 1.0, rng)
+    }
+
+    // A universe under `rule`, e.g. `B2/S34H`, with enough cells to fill the
+    // canvas. Hexagons and triangles are drawn `CELL_PIXELS` tall.
+    pub fn with_rule(rule: &str, seed: Option<u64>) -> Result<Universe, JsValue> {
+        let rule: life_core::Rule = rule
+            .parse()
+            .map_err(|err: life_core::ParseRuleError| JsValue::from_str(&err.to_string()))?;
+        let lattice = rule.lattice();
+        let scale = match lattice {
+            life_core::Lattice::Square => 1.0,
+            _ => CELL_PIXELS as f64,
+        };
+        let height = (CHECKERBOARD_SIZE as f64 / scale).ceil() as usize;
+        let width = (CHECKERBOARD_SIZE as f64 / (scale * lattice.column_width())).ceil() as usize;
+        let mut rng = seeded_rng(seed);
+        let mut inner = life_core::Universe::new(width, height);
+        inner.set_rule(rule);
+        inner.randomize(|| rng.gen_bool(1.0 / 2.0));
+        // Under a colour rule the live cells share the colours equally.
+        if rule.colors() > 1 {
+            for h in 0..height {
+                for w in 0..width {
+                    if inner.is_alive(w, h) {
+                        inner.set_family(w, h, 1 + rng.below(rule.colors() as u64) as u8);
+                    }
+                }
             }
-            board.push(line);
         }
-        Board {
-            board,
-            width,
-            height,
+        Ok(Universe::from_parts(inner, scale, rng))
+    }
+
+    pub fn tick(&mut self) {
+        self.inner.tick();
+
+        let buf = palette(&mut self.rng);
+        let (width, height) = (self.inner.width(), self.inner.height());
+        self.noise
+            .apply(&mut self.inner, width, height, &mut self.rng);
+        self.history.record(&self.inner);
+        self.draw(&buf, false);
+    }
+
+    // Switches the order cells are updated in: `sync`, `sweep`, `random` or
+    // `alpha=0.5`, see `life_core::Update`.
+    pub fn set_update(&mut self, update: &str) -> Result<(), JsValue> {
+        let update: life_core::Update = update
+            .parse()
+            .map_err(|err: life_core::ParseRuleError| JsValue::from_str(&err.to_string()))?;
+        let seed = self.rng.next_u64();
+        self.inner.set_update(update, seed);
+        Ok(())
+    }
+
+    // Replaces the noise laid over every tick; an empty string turns it off.
+    pub fn set_noise(&mut self, noise: &str) -> Result<(), JsValue> {
+        self.noise = parse_noise(noise)?;
+        Ok(())
+    }
+
+    pub fn generation(&self) -> f64 {
+        self.inner.generation() as f64
+    }
+
+    // The oldest and newest generations that can be gone back to.
+    pub fn history_first(&self) -> f64 {
+        self.history.first().unwrap_or(0) as f64
+    }
+
+    pub fn history_last(&self) -> f64 {
+        self.history.last().unwrap_or(0) as f64
+    }
+
+    // Goes back to the latest generation kept up to `generation`. Ticking
+    // from there branches off, dropping the generations that came after it.
+    pub fn scrub(&mut self, generation: f64) {
+        if self
+            .history
+            .restore(&mut self.inner, generation as u64)
+            .is_some()
+        {
+            // Seeded by the generation, so looking back leaves the run's own
+            // generator alone.
+            let buf = palette(&mut life_core::Rng::new(self.inner.generation()));
+            self.draw(&buf, true);
+        }
+    }
+
+    pub fn step_back(&mut self) {
+        if let Some(generation) = self.history.before(self.inner.generation()) {
+            self.scrub(generation as f64);
         }
     }
-    pub fn set(&mut self, w: usize, h: usize, cell: Cell) {
-        self.board[h][w] = cell
+
+    // Goes forward through the history, or ticks once past its end.
+    pub fn step_forward(&mut self) {
+        match self.history.after(self.inner.generation()) {
+            Some(generation) => self.scrub(generation as f64),
+            None => self.tick(),
+        }
     }
+}
 
-    pub fn is_alive(&self, w: usize, h: usize) -> bool {
-        self.board[h][w].live == Live::Alive
+impl Universe {
+    fn from_parts(inner: life_core::Universe, scale: f64, rng: life_core::Rng) -> Universe {
+        let mut history = life_core::History::new(HISTORY_SNAPSHOT_EVERY, HISTORY_LENGTH);
+        history.record(&inner);
+        Universe {
+            inner,
+            scale,
+            rng,
+            noise: parse_noise(DEFAULT_NOISE).unwrap(),
+            history,
+        }
     }
-    pub fn alive_neighbors_count(&self, w: usize, h: usize) -> u8 {
-        let mut count: u8 = 0;
-        for dir in DIRECTIONS {
-            let _w: i32 = w as i32 + dir[0];
-            let _h = h as i32 + dir[1];
-            if _w < 0 || _w as usize >= self.width || _h < 0 || _h as usize >= self.height {
-                continue;
-            }
-            if self.is_alive(_w as usize, _h as usize) {
-                count += 1;
+
+    // Paints the cells born this generation in colours from `buf`, or every
+    // live cell when `repaint` is set. The dying cells of Generations rules
+    // darken a step each generation, the more the closer they are to dead.
+    fn draw(&self, buf: &[u8], repaint: bool) {
+        let (width, height) = (self.inner.width(), self.inner.height());
+        let now = self.inner.get_now_board();
+        let prev = self.inner.get_pre_board();
+        let lattice = self.inner.rule().lattice();
+        let colors = self.inner.rule().colors();
+        let states = self.inner.rule().states() as u32;
+        unsafe {
+            for py in 0..CHECKERBOARD_SIZE {
+                for px in 0..CHECKERBOARD_SIZE {
+                    let square_number: usize = py * CHECKERBOARD_SIZE + px;
+                    let square_rgba_index: usize = square_number * 4;
+
+                    // Which cell covers the middle of this pixel
+                    let (x, y) = lattice.cell_at(
+                        (px as f64 + 0.5) / self.scale,
+                        (py as f64 + 0.5) / self.scale,
+                    );
+                    let cell = (x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height)
+                        .then_some((x as usize, y as usize));
+
+                    match cell {
+                        Some((w, h)) if now.is_alive(w, h) => {
+                            if colors > 1 {
+                                // Colour rules paint each cell in its family's colour.
+                                let color = FAMILIES[now.get(w, h).family as usize - 1];
+                                OUTPUT_BUFFER[square_rgba_index..square_rgba_index + 4]
+                                    .copy_from_slice(&color);
+                            } else if repaint || !prev.is_alive(w, h) {
+                                let n = (w + h) % 3;
+                                OUTPUT_BUFFER[square_rgba_index] = buf[n]; // Red
+                                OUTPUT_BUFFER[square_rgba_index + 1] = buf[n + 1]; // Green
+                                OUTPUT_BUFFER[square_rgba_index + 2] = buf[n + 2]; // Blue
+                                OUTPUT_BUFFER[square_rgba_index + 3] = 255; // Alpha (Always Opaque)
+                            }
+                        }
+                        Some((w, h)) if now.get(w, h).live != life_core::Live::Dead => {
+                            let state = now.get(w, h).live.state() as u32;
+                            let pixel =
+                                &mut OUTPUT_BUFFER[square_rgba_index..square_rgba_index + 3];
+                            // Repainting starts again from a newborn's colour,
+                            // otherwise the pixel already holds last
+                            // generation's shade.
+                            let (from, to) = if repaint {
+                                let n = (w + h) % 3;
+                                pixel.copy_from_slice(&buf[n..n + 3]);
+                                (states - 1, states - state)
+                            } else {
+                                (states - state + 1, states - state)
+                            };
+                            for c in pixel {
+                                *c = (*c as u32 * to / from) as u8;
+                            }
+                            OUTPUT_BUFFER[square_rgba_index + 3] = 255; // Alpha (Always Opaque)
+                        }
+                        _ => {
+                            OUTPUT_BUFFER[square_rgba_index] = 0; // Red
+                            OUTPUT_BUFFER[square_rgba_index + 1] = 0; // Green
+                            OUTPUT_BUFFER[square_rgba_index + 2] = 0; // Blue
+                            OUTPUT_BUFFER[square_rgba_index + 3] = 255; // Alpha (Always Opaque)
+                        }
+                    }
+                }
             }
         }
-        count
     }
 }
 
-#[wasm_bindgen]
-extern "C" {
-    // Use `js_namespace` here to bind `console.log(..)` instead of just
-    // `log(..)`
-    #[wasm_bindgen(js_namespace = console)]
-    fn log(s: &str);
+// The RGBA colours of colour rules such as Immigration and QuadLife.
+const FAMILIES: [[u8; 4]; 4] = [
+    [255, 0, 0, 255],
+    [0, 0, 255, 255],
+    [0, 255, 0, 255],
+    [255, 255, 0, 255],
+];
 
-    // The `console.log` is quite polymorphic, so we can bind it with multiple
-    // signatures. Note that we need to use `js_name` to ensure we always call
-    // `log` in JS.
-    #[wasm_bindgen(js_namespace = console, js_name = log)]
-    fn log_u32(a: u32);
+// Random bytes to colour a generation's newborn cells with.
+fn palette(rng: &mut life_core::Rng) -> Vec<u8> {
+    [rng.next_u64(), rng.next_u64()]
+        .iter()
+        .flat_map(|n| n.to_le_bytes())
+        .collect()
+}
 
-    // Multiple arguments too!
-    #[wasm_bindgen(js_namespace = console, js_name = log)]
-    fn log_many(a: &str, b: &str);
+// A board on an engine of its own, each state drawn in a colour of its own:
+// a Golly `.rule` file's `@COLORS`, white on black for block rules, or a
+// colour map for continuous ones.
+#[wasm_bindgen]
+pub struct EngineUniverse {
+    inner: Box<dyn life_core::Engine>,
+    // Side of the board, centred on the canvas where it is larger
+    size: usize,
+    colors: Vec<life_core::Color>,
+    rng: life_core::Rng,
+    // Random births and deaths after every tick, none unless the page asks
+    noise: life_core::Noise,
+    history: life_core::History,
 }
 
+// Whether `rule` is a Margolus block rule, e.g. `Critters` or `MS,D...`, for
+// `EngineUniverse::with_block_rule`.
 #[wasm_bindgen]
-pub struct Universe {
-    twin: Vec<Board>,
-    iboard: usize,
-    now: u64,
-    width: usize,
-    height: usize,
+pub fn is_block_rule(rule: &str) -> bool {
+    rule.parse::<life_core::BlockRule>().is_ok()
 }
 
+// Whether `rule` is a Lenia or SmoothLife rule, e.g. `Lenia` or
+// `R=13,T=10,m=0.15,s=0.015`, for `EngineUniverse::with_continuous_rule`.
 #[wasm_bindgen]
-impl Universe {
-    #[wasm_bindgen(constructor)]
-    pub fn new(width: usize, height: usize) -> Universe {
-        let mut u: Universe = Universe {
-            iboard: 0,
-            twin: vec![],
-            now: 0,
-            width: width,
-            height: height,
-        };
-        u.twin.push(Board::new(width, height));
-        u.twin.push(Board::new(width, height));
+pub fn is_continuous_rule(rule: &str) -> bool {
+    rule.parse::<life_core::ContinuousRule>().is_ok()
+}
 
-        for h in 0..height {
-            for w in 0..width {
-                let mut buf = [0u8; 1];
-                getrandom::getrandom(&mut buf);
+#[wasm_bindgen]
+impl EngineUniverse {
+    // Parses the text of a `.rule` file and fills the canvas with a random
+    // soup of its states.
+    pub fn with_rule_file(text: &str, seed: Option<u64>) -> Result<EngineUniverse, JsValue> {
+        let rule: life_core::RuleFile = text
+            .parse()
+            .map_err(|err: life_core::ParseRuleError| JsValue::from_str(&err.to_string()))?;
+        let colors = (0..rule.states()).map(|state| rule.color(state)).collect();
+        let inner = life_core::RuleLoader::new(CHECKERBOARD_SIZE, CHECKERBOARD_SIZE, rule);
+        Ok(EngineUniverse::from_parts(
+            Box::new(inner),
+            CHECKERBOARD_SIZE,
+            colors,
+            0..CHECKERBOARD_SIZE,
+            seed,
+        ))
+    }
 
-                let mut live: Live = Live::Alive;
-                if buf[0] % 2 == 1 {
-                    live = Live::Dead;
-                }
-                u.twin[0].set(w, h, Cell { live, birth_day: 0 })
-            }
-        }
-        u
+    // A torus under a Margolus block rule, filled with a random soup.
+    pub fn with_block_rule(rule: &str, seed: Option<u64>) -> Result<EngineUniverse, JsValue> {
+        let rule: life_core::BlockRule = rule
+            .parse()
+            .map_err(|err: life_core::ParseRuleError| JsValue::from_str(&err.to_string()))?;
+        let inner = life_core::Margolus::with_topology(
+            CHECKERBOARD_SIZE,
+            CHECKERBOARD_SIZE,
+            rule,
+            life_core::Topology::Torus,
+        );
+        let colors = vec![life_core::BLACK, [1.0; 4]];
+        Ok(EngineUniverse::from_parts(
+            Box::new(inner),
+            CHECKERBOARD_SIZE,
+            colors,
+            0..CHECKERBOARD_SIZE,
+            seed,
+        ))
     }
-    fn get_board(&self, i: usize) -> &Board {
-        &self.twin[i]
+
+    // A torus under a Lenia or SmoothLife rule, with a patch of noise in the
+    // middle, each cell drawn through `life_core::color_map`. The torus is a
+    // power of two across, so the neighbourhoods are summed by FFT, and the
+    // canvas shows the middle of it.
+    pub fn with_continuous_rule(rule: &str, seed: Option<u64>) -> Result<EngineUniverse, JsValue> {
+        let rule: life_core::ContinuousRule = rule
+            .parse()
+            .map_err(|err: life_core::ParseRuleError| JsValue::from_str(&err.to_string()))?;
+        let size = CHECKERBOARD_SIZE.next_power_of_two();
+        let inner = life_core::ContinuousLife::new(size, size, rule);
+        let colors = (0..=255)
+            .map(|state| life_core::color_map(state as f64 / 255.0))
+            .collect();
+        Ok(EngineUniverse::from_parts(
+            Box::new(inner),
+            size,
+            colors,
+            size / 4..size * 3 / 4,
+            seed,
+        ))
     }
 
     pub fn tick(&mut self) {
-        let prev_i = self.iboard;
-        let now_i = (prev_i + 1) % 2;
-        self.now += 1;
+        self.inner.tick();
+        self.noise
+            .apply(self.inner.as_mut(), self.size, self.size, &mut self.rng);
+        self.history.record(self.inner.as_ref());
+        self.draw();
+    }
 
-        let mut buf = [0u8; 10];
-        getrandom::getrandom(&mut buf).unwrap();
-        let timenow = chrono::Utc::now().timestamp_millis() as u64;
-
-        for h in 0..self.height {
-            for w in 0..self.width {
-                let mut live: Live;
-                let neighbors = self.get_board(prev_i).alive_neighbors_count(w, h);
-                match neighbors {
-                    0..=1 => {
-                        live = Live::Dead;
-                    }
-                    3 => {
-                        live = Live::Alive;
-                    }
-                    4..=u8::MAX => {
-                        live = Live::Dead;
-                    }
-                    _ => {
-                        if self.get_board(prev_i).is_alive(w, h) {
-                            live = Live::Alive;
-                        } else {
-                            live = Live::Dead;
-                        }
-                    }
-                }
-                //
-                if live == Live::Dead
-                    && timenow
-                        % (CHECKERBOARD_SIZE + (w + 1) * (h + 1) + 9873 + neighbors as usize) as u64
-                        == 0
-                {
-                    live = Live::Alive;
-                }
-                self.twin[now_i].set(
-                    w,
-                    h,
-                    Cell {
-                        live,
-                        birth_day: self.now,
-                    },
-                )
+    pub fn set_noise(&mut self, noise: &str) -> Result<(), JsValue> {
+        self.noise = parse_noise(noise)?;
+        Ok(())
+    }
+
+    pub fn generation(&self) -> f64 {
+        self.inner.generation() as f64
+    }
+
+    pub fn history_first(&self) -> f64 {
+        self.history.first().unwrap_or(0) as f64
+    }
+
+    pub fn history_last(&self) -> f64 {
+        self.history.last().unwrap_or(0) as f64
+    }
+
+    pub fn scrub(&mut self, generation: f64) {
+        if self
+            .history
+            .restore(self.inner.as_mut(), generation as u64)
+            .is_some()
+        {
+            self.draw();
+        }
+    }
+
+    pub fn step_back(&mut self) {
+        if let Some(generation) = self.history.before(self.inner.generation()) {
+            self.scrub(generation as f64);
+        }
+    }
+
+    pub fn step_forward(&mut self) {
+        match self.history.after(self.inner.generation()) {
+            Some(generation) => self.scrub(generation as f64),
+            None => self.tick(),
+        }
+    }
+}
+
+impl EngineUniverse {
+    // Fills the square `soup` x `soup` of `inner` with every state in
+    // `colors` equally often.
+    fn from_parts(
+        mut inner: Box<dyn life_core::Engine>,
+        size: usize,
+        colors: Vec<life_core::Color>,
+        soup: std::ops::Range<usize>,
+        seed: Option<u64>,
+    ) -> EngineUniverse {
+        let mut rng = seeded_rng(seed);
+        for h in soup.clone() {
+            for w in soup.clone() {
+                inner.set_state(w as i64, h as i64, rng.below(colors.len() as u64) as u8);
             }
         }
-        self.iboard = now_i;
+        let mut history = life_core::History::new(HISTORY_SNAPSHOT_EVERY, HISTORY_LENGTH);
+        history.record(inner.as_ref());
+        EngineUniverse {
+            inner,
+            size,
+            colors,
+            rng,
+            noise: life_core::Noise::default(),
+            history,
+        }
+    }
 
+    fn draw(&self) {
+        let offset = (self.size - CHECKERBOARD_SIZE) as i64 / 2;
         unsafe {
-            for h in 0..self.height {
-                for w in 0..self.width {
-                    let square_number: usize = h * CHECKERBOARD_SIZE + w;
-                    let square_rgba_index: usize = square_number * 4;
-                    let n = (w + h) % 3;
-
-                    if self.get_board(now_i).is_alive(w, h) {
-                        if self.get_board(prev_i).is_alive(w, h) {
-                        } else {
-                            OUTPUT_BUFFER[square_rgba_index] = buf[n]; // Red
-                            OUTPUT_BUFFER[square_rgba_index + 1] = buf[n + 1]; // Green
-                            OUTPUT_BUFFER[square_rgba_index + 2] = buf[n + 2]; // Blue
-                            OUTPUT_BUFFER[square_rgba_index + 3] = 255; // Alpha (Always Opaque)
-                        }
-                    } else {
-                        OUTPUT_BUFFER[square_rgba_index] = 0; // Red
-                        OUTPUT_BUFFER[square_rgba_index + 1] = 0; // Green
-                        OUTPUT_BUFFER[square_rgba_index + 2] = 0; // Blue
-                        OUTPUT_BUFFER[square_rgba_index + 3] = 255; // Alpha (Always Opaque)
-                    }
+            for h in 0..CHECKERBOARD_SIZE {
+                for w in 0..CHECKERBOARD_SIZE {
+                    let square_rgba_index = (h * CHECKERBOARD_SIZE + w) * 4;
+                    let state = self.inner.state(w as i64 + offset, h as i64 + offset);
+                    let color = self.colors[state as usize];
+                    OUTPUT_BUFFER[square_rgba_index] = (color[0] * 255.0) as u8; // Red
+                    OUTPUT_BUFFER[square_rgba_index + 1] = (color[1] * 255.0) as u8; // Green
+                    OUTPUT_BUFFER[square_rgba_index + 2] = (color[2] * 255.0) as u8; // Blue
+                    OUTPUT_BUFFER[square_rgba_index + 3] = 255; // Alpha (Always Opaque)
                 }
             }
         }